@@ -0,0 +1,455 @@
+//! Generates the router of the pages from the content of `src/pages` (or the `path.pages` set in
+//! `wini.toml`).
+//!
+//! Every module declared from `src/pages/mod.rs` is visited, and every function marked with
+//! `#[page]` is registered:
+//! - Directory names map to URL segments: `pages/doc/mod.rs` is served on `/doc`
+//! - `[param]` directories become path parameters: `pages/user/[id]/mod.rs` is served on
+//!   `/user/{id}`
+//! - `[...param]` directories catch all the remaining segments: `pages/doc/[...page]/mod.rs` is
+//!   served on `/doc/{*page}`
+//! - A page function named `render` is served on the route of its module. Other page functions
+//!   are served on a sub-route of the same name.
+//! - An `#[action]` function is served on the route of its page (`render` by default), with its
+//!   method (`POST` by default).
+//! - A `layout` module with a `#[layout]` function, applies this layout to every page below its
+//!   directory.
+//! - The panics of the pages and actions are converted into errors, rendered by the layouts.
+//! - The responses of the pages with an error status code and an empty body are replaced by the
+//!   page of their status code, also rendered by the layouts.
+//!
+//! The router has the state `crate::shared::app_state::AppState`, which is also given to the
+//! layouts.
+
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+/// The state of the router, shared by the pages and the layouts
+const STATE_TYPE: &str = "crate::shared::app_state::AppState";
+
+/// A module from `src/pages`
+struct Module {
+    /// The path of the module in the crate: `crate::pages::doc`
+    rust_path: String,
+    /// The file of the module: `src/pages/doc/mod.rs`
+    file: PathBuf,
+    /// The URL segments on which the module is served
+    url_segments: Vec<String>,
+    /// The `#[page]` functions of this module
+    pages: Vec<String>,
+    /// The `#[action]` functions of this module
+    actions: Vec<Action>,
+    /// The `#[layout]` function of this module
+    layout: Option<String>,
+    children: Vec<Module>,
+}
+
+/// An `#[action]` function
+struct Action {
+    name: String,
+    /// The page on which it's served
+    page: String,
+    /// In lowercase, as the function of `axum::routing::MethodRouter`
+    method: String,
+}
+
+impl Module {
+    fn has_pages(&self) -> bool {
+        !self.pages.is_empty() || self.children.iter().any(Module::has_pages)
+    }
+
+    fn has_layouts(&self) -> bool {
+        self.has_pages() && (self.layout.is_some() || self.children.iter().any(Module::has_layouts))
+    }
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=wini.toml");
+
+    let pages_dir = pages_dir();
+    println!("cargo:rerun-if-changed={}", pages_dir.display());
+
+    let root_file = pages_dir.join("mod.rs");
+    let module_name = pages_dir
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("pages")
+        .to_owned();
+
+    let root = visit_module(
+        &root_file,
+        &pages_dir,
+        format!("crate::{module_name}"),
+        Vec::new(),
+    );
+
+    // The state is only used by the layouts
+    let state = if root.has_layouts() {
+        "state"
+    } else {
+        "_state"
+    };
+
+    let mut generated = String::from(
+        "/// The router of all the `#[page]`s, generated by `build.rs` from the content of the \
+         pages directory.\n",
+    );
+    let _ = writeln!(
+        generated,
+        "pub fn pages_router({state}: {STATE_TYPE}) -> axum::Router<{STATE_TYPE}> {{\n    {}\n}}",
+        router_of(&root)
+    );
+
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").expect("Set by cargo"));
+    std::fs::write(out_dir.join("pages_router.rs"), generated)
+        .expect("Couldn't write the router of the pages");
+    std::fs::write(out_dir.join("url_for.rs"), url_for_of(&root))
+        .expect("Couldn't write the `url_for!` macro");
+}
+
+/// The directory of the pages, as specified in `wini.toml`
+fn pages_dir() -> PathBuf {
+    let pages = std::fs::read_to_string("./wini.toml")
+        .ok()
+        .and_then(|config| config.parse::<toml::Table>().ok())
+        .and_then(|config| {
+            config
+                .get("path")?
+                .get("pages")?
+                .as_str()
+                .map(str::to_owned)
+        })
+        .unwrap_or_else(|| "./pages".to_owned());
+
+    Path::new("src").join(pages.trim_start_matches("./"))
+}
+
+/// Visit a module file and all the modules it declares
+fn visit_module(file: &Path, dir: &Path, rust_path: String, url_segments: Vec<String>) -> Module {
+    let mut module = Module {
+        rust_path,
+        file: file.to_path_buf(),
+        url_segments,
+        pages: Vec::new(),
+        actions: Vec::new(),
+        layout: None,
+        children: Vec::new(),
+    };
+
+    let Ok(content) = std::fs::read_to_string(file) else {
+        return module;
+    };
+    let Ok(ast) = syn::parse_file(&content) else {
+        // Let rustc report the error
+        return module;
+    };
+
+    for item in ast.items {
+        match item {
+            syn::Item::Fn(function) => {
+                if has_attribute(&function.attrs, "page") {
+                    module.pages.push(function.sig.ident.to_string());
+                } else if let Some(attr) = function
+                    .attrs
+                    .iter()
+                    .find(|attr| has_attribute(std::slice::from_ref(attr), "action"))
+                {
+                    module
+                        .actions
+                        .push(action_of(function.sig.ident.to_string(), attr));
+                }
+            },
+            syn::Item::Mod(item_mod) if item_mod.content.is_none() => {
+                let name = item_mod.ident.to_string();
+
+                let Some(child_file) = module_file(dir, &name, &item_mod.attrs) else {
+                    continue;
+                };
+                let child_dir = if child_file.ends_with("mod.rs") {
+                    child_file.parent().unwrap_or(dir).to_path_buf()
+                } else {
+                    child_file.with_extension("")
+                };
+
+                if name == "layout" {
+                    module.layout = layout_of(&child_file)
+                        .map(|layout| format!("{}::layout::{layout}", module.rust_path));
+                    continue;
+                }
+
+                // The name of the segment is the name of the file or directory, since the name of
+                // the module can't contain `[` or `]`
+                let segment = if child_file.ends_with("mod.rs") {
+                    child_dir.file_name()
+                } else {
+                    child_file.file_stem()
+                }
+                .and_then(|segment| segment.to_str())
+                .unwrap_or(&name);
+
+                let mut url_segments = module.url_segments.clone();
+                url_segments.push(url_segment(segment));
+
+                module.children.push(visit_module(
+                    &child_file,
+                    &child_dir,
+                    format!("{}::{name}", module.rust_path),
+                    url_segments,
+                ));
+            },
+            _ => {},
+        }
+    }
+
+    module
+}
+
+/// Find the file of a module declared as `mod name;` in `dir`
+fn module_file(dir: &Path, name: &str, attrs: &[syn::Attribute]) -> Option<PathBuf> {
+    let custom_path = attrs.iter().find_map(|attr| {
+        if attr.path().is_ident("path") &&
+            let syn::Meta::NameValue(name_value) = &attr.meta &&
+            let syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(path),
+                ..
+            }) = &name_value.value
+        {
+            Some(dir.join(path.value()))
+        } else {
+            None
+        }
+    });
+
+    custom_path
+        .into_iter()
+        .chain([
+            dir.join(format!("{name}.rs")),
+            dir.join(name).join("mod.rs"),
+        ])
+        .find(|path| path.is_file())
+}
+
+/// The name of the `#[layout]` function in a file
+fn layout_of(file: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(file).ok()?;
+    let ast = syn::parse_file(&content).ok()?;
+
+    ast.items.into_iter().find_map(|item| {
+        match item {
+            syn::Item::Fn(function) if has_attribute(&function.attrs, "layout") => {
+                Some(function.sig.ident.to_string())
+            },
+            _ => None,
+        }
+    })
+}
+
+/// Read the parameters of an `#[action]`
+fn action_of(name: String, attr: &syn::Attribute) -> Action {
+    let mut action = Action {
+        name,
+        page: "render".to_owned(),
+        method: "post".to_owned(),
+    };
+
+    if matches!(attr.meta, syn::Meta::List(_)) {
+        // Let the macro report invalid parameters
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("page") {
+                action.page = meta.value()?.parse::<syn::Ident>()?.to_string();
+            } else if meta.path.is_ident("method") {
+                action.method = meta.value()?.parse::<syn::LitStr>()?.value().to_lowercase();
+            }
+            Ok(())
+        });
+    }
+
+    action
+}
+
+fn has_attribute(attrs: &[syn::Attribute], name: &str) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path()
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == name)
+    })
+}
+
+/// Convert the name of a directory to a segment of the route
+fn url_segment(name: &str) -> String {
+    if let Some(param) = name
+        .strip_prefix("[...")
+        .and_then(|name| name.strip_suffix(']'))
+    {
+        format!("{{*{param}}}")
+    } else if let Some(param) = name
+        .strip_prefix('[')
+        .and_then(|name| name.strip_suffix(']'))
+    {
+        format!("{{{param}}}")
+    } else {
+        name.to_owned()
+    }
+}
+
+fn route_of(segments: &[String]) -> String {
+    format!("/{}", segments.join("/"))
+}
+
+/// The URL segments on which a page of a module is served
+fn segments_of_page(module: &Module, page: &str) -> Vec<String> {
+    let mut segments = module.url_segments.clone();
+    if page != "render" {
+        segments.push(page.to_owned());
+    }
+    segments
+}
+
+/// Generate the expression of the router of a module
+fn router_of(module: &Module) -> String {
+    let mut router = String::from("axum::Router::new()");
+
+    for page in &module.pages {
+        let route = route_of(&segments_of_page(module, page));
+
+        let _ = write!(
+            router,
+            ".route({route:?}, axum::routing::get({}::{page}){}",
+            module.rust_path,
+            catch_panic_of(module, page)
+        );
+        for action in module.actions.iter().filter(|action| action.page == *page) {
+            let _ = write!(
+                router,
+                ".merge(axum::routing::{}({}::{}){})",
+                action.method,
+                module.rust_path,
+                action.name,
+                catch_panic_of(module, &action.name)
+            );
+        }
+        router.push_str(
+            ".layer(axum::middleware::from_fn(crate::utils::wini::status_pages::status_pages)))",
+        );
+    }
+
+    for child in module.children.iter().filter(|child| child.has_pages()) {
+        let _ = write!(router, ".merge({})", router_of(child));
+    }
+
+    if let Some(layout) = &module.layout {
+        let _ = write!(
+            router,
+            ".layer(axum::middleware::from_fn_with_state(state.clone(), {layout}::<{STATE_TYPE}>))"
+        );
+    }
+
+    router
+}
+
+/// The layer that converts the panics of a function of a module into errors
+fn catch_panic_of(module: &Module, function: &str) -> String {
+    format!(
+        ".layer(axum::middleware::from_fn_with_state(\
+         crate::shared::wini::err::Trace {{ file_path: {:?}, function_name: {function:?} }}, \
+         crate::utils::wini::panic::catch_panic))",
+        module.file.display().to_string()
+    )
+}
+
+
+/// Generate the `url_for!` macro, with a rule for each page
+fn url_for_of(root: &Module) -> String {
+    let mut rules = String::new();
+    add_url_for_rules(root, &mut rules);
+
+    format!(
+        "/// Build the URL of a `#[page]`, with its parameters checked at compile time.\n\
+         ///\n\
+         /// Parameters are given in the order in which they appear in the route.\n\
+         ///\n\
+         /// # Example\n\
+         ///\n\
+         /// ```ignore\n\
+         /// url_for!(pages::doc::page::render, page = \"layout\"); // \"/doc/layout\"\n\
+         /// ```\n\
+         ///\n\
+         /// Generated by `build.rs` from the content of the pages directory.\n\
+         #[macro_export]\n\
+         #[allow(\n    \
+             clippy::crate_in_macro_def,\n    \
+             reason = \"`crate::` is matched literally, to refer to the pages by their full path\"\n\
+         )]\n\
+         macro_rules! url_for {{\n\
+         {rules}    \
+             ($($tokens:tt)*) => {{\n        \
+                 compile_error!(concat!(\"No page or parameters match `\", stringify!($($tokens)*), \"`\"))\n    \
+             }};\n\
+         }}\n"
+    )
+}
+
+fn add_url_for_rules(module: &Module, rules: &mut String) {
+    let path = module.rust_path.trim_start_matches("crate::");
+
+    for page in &module.pages {
+        let mut params = Vec::new();
+        let mut format_args = Vec::new();
+        let mut format_str = String::new();
+
+        for segment in segments_of_page(module, page) {
+            format_str.push('/');
+
+            let (param, is_catch_all) = if let Some(param) = segment
+                .strip_prefix("{*")
+                .and_then(|segment| segment.strip_suffix('}'))
+            {
+                (Some(param.to_owned()), true)
+            } else if let Some(param) = segment
+                .strip_prefix('{')
+                .and_then(|segment| segment.strip_suffix('}'))
+            {
+                (Some(param.to_owned()), false)
+            } else {
+                (None, false)
+            };
+
+            if let Some(param) = param {
+                format_str.push_str("{}");
+                params.push(format!(", {param} = ${param}:expr"));
+                format_args.push(format!(
+                    ", $crate::utils::wini::path::encode_path_segment(&${param}, {is_catch_all})"
+                ));
+            } else {
+                format_str.push_str(&segment);
+            }
+        }
+
+        if format_str.is_empty() {
+            format_str.push('/');
+        }
+
+        let params = params.concat();
+        let url = if format_args.is_empty() {
+            format!("::std::string::String::from({format_str:?})")
+        } else {
+            format!("format!({format_str:?}{})", format_args.concat())
+        };
+
+        for prefix in ["", "crate::"] {
+            let _ = writeln!(
+                rules,
+                "    ({prefix}{path}::{page}{params} $(,)?) => {{\n        \
+                 {url}\n    \
+                 }};"
+            );
+        }
+    }
+
+    for child in &module.children {
+        add_url_for_rules(child, rules);
+    }
+}
@@ -17,6 +17,9 @@ pub(crate) mod utils;
 /// # Parameters
 ///
 /// - `js_pkgs` - Array of JavaScript package names to include
+/// - `defer` - Render the component after the rest of the page, which is streamed without waiting
+///   for it. Its parameters must be owned, and its errors are rendered in its place: it always
+///   returns `Markup`.
 ///
 /// # Return Types
 ///
@@ -227,6 +230,11 @@ pub fn component(args: TokenStream, item: TokenStream) -> TokenStream {
 /// request/respone data.
 /// They automatically link JS/CSS files and support SEO meta tags.
 ///
+/// Layouts are generic over the state of the router, so they are applied with:
+/// ```rust,ignore
+/// router.layer(axum::middleware::from_fn_with_state(state, my_layout::<AppState>))
+/// ```
+///
 /// # Parameters
 ///
 /// - `title` - Page title (sets `<title>` and `og:title`)
@@ -248,6 +256,9 @@ pub fn component(args: TokenStream, item: TokenStream) -> TokenStream {
 /// - [`crate::shared::wini::response::FromResponseBody`]
 /// - [`crate::shared::wini::response::FromResponseParts`]
 ///
+/// These traits are implemented for the state of the router, so extractors like
+/// `State<AppState>` can be used.
+///
 /// There are just a few rules:
 /// 1. Arguments that come from `FromResponseBody` MUST be the last argument.
 /// 2. Only one argument can come from `FromResponseBody`.
@@ -275,6 +286,25 @@ pub fn component(args: TokenStream, item: TokenStream) -> TokenStream {
 /// }
 /// ```
 ///
+/// ## With the state of the router
+///
+/// ```rust,ignore
+/// use {
+///     axum::extract::State,
+///     maud::{html, Markup},
+///     wini_macros::layout,
+///     crate::shared::app_state::AppState,
+/// };
+///
+/// #[layout]
+/// pub async fn with_state(State(state): State<AppState>, child: Markup) -> Markup {
+///     html! {
+///         header { (state.site_name) }
+///         (child)
+///     }
+/// }
+/// ```
+///
 /// ## With conflict between implementations
 ///
 /// ```rust,ignore
@@ -478,6 +508,22 @@ pub fn layout(args: TokenStream, item: TokenStream) -> TokenStream {
 /// }
 /// ```
 ///
+/// ## With the state of the router
+///
+/// ```rust,ignore
+/// use {axum::extract::State, maud::{html, Markup}, wini_macros::page};
+///
+/// #[page]
+/// pub async fn users(State(state): State<AppState>) -> ServerResult<Markup> {
+///     let users = state.db.users().await?;
+///     Ok(html! {
+///         @for user in users {
+///             p { (user.name) }
+///         }
+///     })
+/// }
+/// ```
+///
 /// ## With error handling
 ///
 /// ```rust,ignore
@@ -579,6 +625,73 @@ pub fn page(args: TokenStream, item: TokenStream) -> TokenStream {
     macros::wini::page::page(args, item)
 }
 
+/// Handles the forms submitted to a page, on the same route as the page.
+///
+/// The first argument of the action is the form: it's deserialized from the URL-encoded body of
+/// the request, and validated with [`Validate`]. The other arguments are extractors, like in pages.
+///
+/// If the form can't be deserialized, isn't valid, or if the action returns
+/// `ActionResponse::Invalid`, the page is rendered again with the status `422`. It can get the
+/// values that were submitted and the errors of the fields with the `ActionFeedback` extractor.
+///
+/// # Parameters
+///
+/// - `method` - `"POST"` (by default), `"PUT"`, `"PATCH"` or `"DELETE"`
+/// - `page` - The page of the module on which the action is served, and which is rendered again if
+///   the form is invalid. `render` by default.
+///
+/// # Return Types
+///
+/// The function can return either `ActionResponse` or `ServerResult<ActionResponse>`.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use {
+///     crate::shared::wini::action::{ActionFeedback, ActionResponse, FieldErrors, Validate},
+///     maud::{html, Markup},
+///     wini_macros::{action, page},
+/// };
+///
+/// #[derive(serde::Deserialize)]
+/// pub struct Subscription {
+///     email: String,
+/// }
+///
+/// impl Validate for Subscription {
+///     fn validate(&self) -> FieldErrors {
+///         let mut errors = FieldErrors::default();
+///         if !self.email.contains('@') {
+///             errors.add("email", "This is not a valid email address.");
+///         }
+///         errors
+///     }
+/// }
+///
+/// #[page]
+/// pub async fn render(feedback: ActionFeedback) -> Markup {
+///     html! {
+///         form method="post" {
+///             input name="email" value=(feedback.value("email"));
+///             @if let Some(error) = feedback.error("email") {
+///                 p { (error) }
+///             }
+///         }
+///     }
+/// }
+///
+/// #[action]
+/// pub async fn subscribe(form: Subscription) -> ActionResponse {
+///     ActionResponse::Redirect("/thanks".to_owned())
+/// }
+/// ```
+///
+/// [`Validate`]: ../wini_website/shared/wini/action/trait.Validate.html
+#[proc_macro_attribute]
+pub fn action(args: TokenStream, item: TokenStream) -> TokenStream {
+    macros::wini::action::action(args, item)
+}
+
 #[proc_macro_attribute]
 pub fn init_cache(args: TokenStream, item: TokenStream) -> TokenStream {
     macros::wini::cache::init_cache(args, item)
@@ -0,0 +1,163 @@
+use {
+    crate::utils::wini::result::is_ouput_ty_result,
+    proc_macro::TokenStream,
+    quote::quote,
+    syn::{FnArg, GenericArgument, Ident, LitStr, PathArguments, Type, parse_macro_input},
+};
+
+
+/// The arguments of `#[action]`
+#[derive(Default)]
+struct ActionParameters {
+    /// The page rendered again when the form is invalid
+    page: Option<Ident>,
+}
+
+impl ActionParameters {
+    fn parse(&mut self, meta: syn::meta::ParseNestedMeta) -> syn::Result<()> {
+        if meta.path.is_ident("page") {
+            self.page = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("method") {
+            // Only used by `build.rs` to generate the router
+            let method: LitStr = meta.value()?.parse()?;
+            match method.value().to_uppercase().as_str() {
+                "POST" | "PUT" | "PATCH" | "DELETE" => Ok(()),
+                _ => Err(meta.error("Actions only support `POST`, `PUT`, `PATCH` and `DELETE`")),
+            }
+        } else {
+            Err(meta.error("Unsupported attribute"))
+        }
+    }
+}
+
+
+/// The type of the state of an action: the one of its `State` extractor, or else the state of the
+/// router generated by `build.rs`
+fn state_ty_of<'a, I: Iterator<Item = &'a FnArg>>(mut inputs: I) -> proc_macro2::TokenStream {
+    inputs
+        .find_map(|input| {
+            let FnArg::Typed(pat_ty) = input else {
+                return None;
+            };
+            let Type::Path(ty) = &*pat_ty.ty else {
+                return None;
+            };
+            let segment = ty
+                .path
+                .segments
+                .last()
+                .filter(|segment| segment.ident == "State")?;
+            let PathArguments::AngleBracketed(args) = &segment.arguments else {
+                return None;
+            };
+
+            match args.args.first() {
+                Some(GenericArgument::Type(state_ty)) => Some(quote!(#state_ty)),
+                _ => None,
+            }
+        })
+        .unwrap_or_else(|| quote!(crate::shared::app_state::AppState))
+}
+
+
+pub fn action(args: TokenStream, item: TokenStream) -> TokenStream {
+    let mut attributes = ActionParameters::default();
+    let attr_parser = syn::meta::parser(|meta| attributes.parse(meta));
+    parse_macro_input!(args with attr_parser);
+
+    let page = attributes
+        .page
+        .unwrap_or_else(|| Ident::new("render", proc_macro2::Span::call_site()));
+
+    // Modify the name of the original function to a reserved one
+    let mut original_function = parse_macro_input!(item as syn::ItemFn);
+    let original_name = original_function.sig.ident.clone();
+    let new_name = Ident::new(
+        &format!("__reserved_fn_wini_{original_name}"),
+        original_name.span(),
+    );
+    original_function.sig.ident = new_name.clone();
+
+    let state_ty = state_ty_of(original_function.sig.inputs.iter());
+    let mut inputs = original_function.sig.inputs.iter();
+
+    // The first argument is the form
+    let form_ty = match inputs.next() {
+        Some(FnArg::Typed(pat_ty)) => pat_ty.ty.clone(),
+        Some(FnArg::Receiver(_)) => panic!("Actions don't support `self`"),
+        None => panic!("Actions must take the submitted form as their first parameter"),
+    };
+
+    // The other arguments are extractors
+    let (extractors, extractor_names): (Vec<_>, Vec<_>) = inputs
+        .enumerate()
+        .map(|(idx, input)| {
+            match input {
+                FnArg::Typed(pat_ty) => {
+                    let name =
+                        Ident::new(&format!("__wini_arg_{idx}"), proc_macro2::Span::call_site());
+                    let ty = &pat_ty.ty;
+                    (quote!(#name: #ty), name)
+                },
+                FnArg::Receiver(_) => panic!("Actions don't support `self`"),
+            }
+        })
+        .unzip();
+
+    let observe_render_duration = quote!(
+        crate::shared::wini::metrics::RENDER_DURATION_SECONDS.observe(
+            &["action", concat!(module_path!(), "::", stringify!(#original_name))],
+            render_start.elapsed(),
+        );
+    );
+
+    let call_inner_action = if is_ouput_ty_result(&original_function) {
+        quote!(
+            match #new_name(form, #(#extractor_names),*).await {
+                Ok(response) => response,
+                Err(err) => {
+                    #observe_render_duration
+
+                    return (&err).into_response();
+                },
+            }
+        )
+    } else {
+        quote!(#new_name(form, #(#extractor_names),*).await)
+    };
+
+    let expanded = quote! {
+        #[allow(non_snake_case)]
+        #original_function
+
+        #[allow(non_snake_case)]
+        pub async fn #original_name(
+            axum::extract::State(__wini_state): axum::extract::State<#state_ty>,
+            #(#extractors,)*
+            __wini_req: axum::extract::Request,
+        ) -> axum::response::Response {
+            use {
+                axum::response::IntoResponse,
+                crate::shared::wini::action::{ActionResponse, Submission},
+            };
+
+            let render_start = std::time::Instant::now();
+
+            let mut submission = Submission::<#form_ty>::from_request(__wini_req).await;
+
+            let response = match submission.validated() {
+                Ok(form) => #call_inner_action,
+                Err(errors) => ActionResponse::Invalid(errors),
+            };
+
+            let resp = submission.respond(response, #page, __wini_state).await;
+
+            #observe_render_duration
+
+            resp
+        }
+    };
+
+    TokenStream::from(expanded)
+}
@@ -1,4 +1,5 @@
 use {
+    crate::utils::wini::linked_files::FileAttribute,
     std::collections::HashMap,
     syn::{ExprArray, Lit, LitStr, meta::ParseNestedMeta},
 };
@@ -26,6 +27,10 @@ pub struct ProcMacroParameters {
     pub other_meta: Option<HashMap<String, String>>,
     /// Add JS packages
     pub js_pkgs: Option<Vec<String>>,
+    /// Files linked with options: `file(url = "./print.css", media = "print")`
+    pub files: Vec<FileAttribute>,
+    /// Render the component after the rest of the page, which is sent without waiting for it
+    pub defer: bool,
 }
 
 macro_rules! generate_extension_function {
@@ -141,6 +146,21 @@ impl ProcMacroParameters {
     pub fn parse(&mut self, meta: ParseNestedMeta) -> syn::Result<()> {
         if let Some(ident) = meta.path.get_ident() {
             match ident.to_string().as_str() {
+                "defer" => {
+                    self.defer = true;
+                    Ok(())
+                },
+                "file" => {
+                    let mut file = FileAttribute::default();
+                    meta.parse_nested_meta(|meta| file.parse(meta))?;
+
+                    if file.url.is_empty() {
+                        return Err(meta.error("Expected the `url` of the file"));
+                    }
+
+                    self.files.push(file);
+                    Ok(())
+                },
                 "other_meta" => {
                     let lit_fake_array: ExprArray = meta.value()?.parse()?;
 
@@ -34,10 +34,21 @@ pub fn init_cache(_args: TokenStream, item: TokenStream) -> TokenStream {
 
 
 
+    // The static created by `#[cached]`, named after the (reserved) name of the function
+    let cache_static_name = Ident::new(
+        &format!("__reserved_fn_wini_{}", &input.sig.ident).to_uppercase(),
+        input.sig.ident.span(),
+    );
+
     let ctor_name = Ident::new(
         &format!("__ctor_initialize_{}", input.sig.ident),
         input.sig.ident.span(),
     );
+    let ctor_metrics_name = Ident::new(
+        &format!("__ctor_register_cache_metrics_{}", input.sig.ident),
+        input.sig.ident.span(),
+    );
+    let fn_name = &input.sig.ident;
 
     let expanded = quote! {
         // The original function with all the code
@@ -53,6 +64,22 @@ pub fn init_cache(_args: TokenStream, item: TokenStream) -> TokenStream {
                 let _ = #cache_fn_name().await;
             });
         }
+
+        // Expose the hits and misses of the cache in `/metrics`
+        #[ctor::ctor]
+        fn #ctor_metrics_name() {
+            crate::shared::wini::metrics::register_function_cache(
+                concat!(module_path!(), "::", stringify!(#fn_name)),
+                || {
+                    Box::pin(async {
+                        use cached::Cached;
+
+                        let cache = #cache_static_name.lock().await;
+                        (cache.cache_hits(), cache.cache_misses())
+                    })
+                },
+            );
+        }
     };
 
     // Convert the generated code back to TokenStream
@@ -1,8 +1,9 @@
 use {
     super::args::ProcMacroParameters,
     crate::utils::wini::{
-        files::{get_current_file_path, get_js_or_css_files_in_current_dir},
+        files::get_current_file_path,
         js_pkgs,
+        linked_files,
         params_from_itemfn::params_from_itemfn,
         result::is_ouput_ty_result,
     },
@@ -54,9 +55,65 @@ pub fn component(args: TokenStream, item: TokenStream) -> TokenStream {
 
     let (arguments, param_names) = params_from_itemfn(&original_function);
 
-    let files_in_current_dir = get_js_or_css_files_in_current_dir();
+    let files_in_current_dir = linked_files::files_in_current_dir(&attributes.files);
 
-    let js_pkgs = js_pkgs::handle(attributes.js_pkgs, quote!(html.linked_files), false);
+    let js_pkgs = js_pkgs::handle(attributes.js_pkgs, quote!(html.linked_files), true);
+    let linked_files = linked_files::handle(&attributes.files, quote!(html.linked_files), true);
+
+    // A deferred component is rendered in its own task, once the page is sent: its errors can't be
+    // propagated anymore, so they are rendered in its place. Its own files are linked to its
+    // placeholder, so that they are in the `<head>` of the page.
+    if attributes.defer {
+        let render = if is_ouput_ty_result(&original_function) {
+            quote!(
+                match #new_name(#(#param_names),*).await {
+                    Ok(html) => html,
+                    Err(mut err) => {
+                        err.add_trace(
+                            crate::shared::wini::err::Trace {
+                                file_path: #current_file_path,
+                                function_name: stringify!(#original_name),
+                            }
+                        );
+
+                        err.render_inline()
+                    },
+                }
+            )
+        } else {
+            quote!(#new_name(#(#param_names),*).await)
+        };
+
+        return TokenStream::from(quote! {
+            #[allow(non_snake_case)]
+            #original_function
+
+            #[allow(non_snake_case)]
+            pub async fn #original_name(#arguments) -> ::maud::Markup {
+                use {
+                    axum::response::IntoResponse,
+                    itertools::Itertools,
+                };
+
+                const FILES_IN_CURRENT_DIR: &[&str] = &[#(#files_in_current_dir),*];
+
+                let mut html = crate::shared::wini::streaming::defer(async move { #render }).await;
+
+                html.linked_files.extend(
+                    FILES_IN_CURRENT_DIR
+                        .iter()
+                        .map(std::ops::Deref::deref)
+                        .map(String::from)
+                );
+
+                #linked_files
+
+                #js_pkgs
+
+                html
+            }
+        });
+    }
 
     // Generate the output code
     let expanded = quote! {
@@ -82,6 +139,8 @@ pub fn component(args: TokenStream, item: TokenStream) -> TokenStream {
             );
             html.linked_files.extend(hashset);
 
+            #linked_files
+
             #js_pkgs
 
             #return_data
@@ -1,11 +1,7 @@
 use {
     crate::{
         macros::wini::args::ProcMacroParameters,
-        utils::wini::{
-            files::get_js_or_css_files_in_current_dir,
-            js_pkgs,
-            result::is_ouput_ty_result,
-        },
+        utils::wini::{js_pkgs, linked_files, result::is_ouput_ty_result},
     },
     proc_macro::TokenStream,
     proc_macro2::Span,
@@ -21,6 +17,10 @@ pub fn layout(args: TokenStream, item: TokenStream) -> TokenStream {
     let attr_parser = syn::meta::parser(|meta| attributes.parse(meta));
     parse_macro_input!(args with attr_parser);
 
+    if attributes.defer {
+        panic!("Only components can be deferred, with `#[component(defer)]`");
+    }
+
     let mut input = parse_macro_input!(item as syn::ItemFn);
 
     // Modify the name of the current input to a reserved one
@@ -59,7 +59,7 @@ pub fn layout(args: TokenStream, item: TokenStream) -> TokenStream {
                 let from_request_parts = quote!(
                     {
                         let (mut req_parts, body) = req.into_parts();
-                        let ty = match #path_ty::__from_request_parts(&mut req_parts, &()).await {
+                        let ty = match #path_ty::__from_request_parts(&mut req_parts, &__wini_state).await {
                             Ok(ok) => ok,
                             Err(into_resp) => return Ok(into_resp.into_response()),
                         };
@@ -70,7 +70,7 @@ pub fn layout(args: TokenStream, item: TokenStream) -> TokenStream {
                 // ResponseParts
                 let from_response_parts = quote!(
                     {
-                        let ty = match #path_ty::__from_response_parts(&mut resp_parts, &()).await {
+                        let ty = match #path_ty::__from_response_parts(&mut resp_parts, &__wini_state).await {
                             Ok(ok) => ok,
                             Err(into_resp) => return Ok(into_resp.into_response()),
                         };
@@ -80,7 +80,7 @@ pub fn layout(args: TokenStream, item: TokenStream) -> TokenStream {
                 // ResponseBody
                 let from_response_body = if is_last {
                     quote!(
-                        match #path_ty::__from_response_body(resp_body, &()).await {
+                        match #path_ty::__from_response_body(resp_body, &__wini_state).await {
                             Ok(ok) => ok,
                             Err(into_resp) => return Ok(into_resp.into_response()),
                         }
@@ -88,7 +88,7 @@ pub fn layout(args: TokenStream, item: TokenStream) -> TokenStream {
                 } else {
                     quote!(
                         const {
-                            if <#ty as IsFromResponseBody>::IS_FROM_RESPONSE_BODY {
+                            if <#ty as IsFromResponseBody<__WiniState>>::IS_FROM_RESPONSE_BODY {
                                 panic!("`FromResponseBody` should always be the last argument");
                             };
                         };
@@ -107,17 +107,17 @@ pub fn layout(args: TokenStream, item: TokenStream) -> TokenStream {
                                     let condition = match impl_from_trait {
                                         FromTrait::ResponseBody => {
                                             quote!(
-                                                <#ty as IsFromResponseBody>::IS_FROM_RESPONSE_BODY
+                                                <#ty as IsFromResponseBody<__WiniState>>::IS_FROM_RESPONSE_BODY
                                             )
                                         },
                                         FromTrait::ResponseParts => {
                                             quote!(
-                                                <#ty as IsFromResponseParts>::IS_FROM_RESPONSE_PARTS
+                                                <#ty as IsFromResponseParts<__WiniState>>::IS_FROM_RESPONSE_PARTS
                                             )
                                         },
                                         FromTrait::RequestParts => {
                                             quote!(
-                                                <#ty as IsFromRequestParts>::IS_FROM_REQUEST_PARTS
+                                                <#ty as IsFromRequestParts<__WiniState>>::IS_FROM_REQUEST_PARTS
                                             )
                                         },
                                     };
@@ -234,17 +234,17 @@ pub fn layout(args: TokenStream, item: TokenStream) -> TokenStream {
                         };
 
                         // Compile-time check to verify the validity of arguments
-                        // The `const` name will appear in the error message, this is why it's
-                        // named like that
-                        const _CHECK_THE_ERROR_MESSAGE_IN_CASE_OF_ERROR: () = const {
+                        // It depends on the state of the router, so it's evaluated once the layout
+                        // is used with a specific state
+                        const {
                             #const_check
 
                             if !can_early_exit {
                                 match
                                 (
-                                    <#ty as IsFromResponseBody>::IS_FROM_RESPONSE_BODY,
-                                    <#ty as IsFromResponseParts>::IS_FROM_RESPONSE_PARTS,
-                                    <#ty as IsFromRequestParts>::IS_FROM_REQUEST_PARTS,
+                                    <#ty as IsFromResponseBody<__WiniState>>::IS_FROM_RESPONSE_BODY,
+                                    <#ty as IsFromResponseParts<__WiniState>>::IS_FROM_RESPONSE_PARTS,
+                                    <#ty as IsFromRequestParts<__WiniState>>::IS_FROM_REQUEST_PARTS,
                                 )
                                 {
                                     // Valid!
@@ -277,9 +277,9 @@ pub fn layout(args: TokenStream, item: TokenStream) -> TokenStream {
                         let dummy: Option<#ty> = #request_attr_condition else {
                             match
                                 (
-                                    <#ty as IsFromResponseBody>::IS_FROM_RESPONSE_BODY,
-                                    <#ty as IsFromResponseParts>::IS_FROM_RESPONSE_PARTS,
-                                    <#ty as IsFromRequestParts>::IS_FROM_REQUEST_PARTS,
+                                    <#ty as IsFromResponseBody<__WiniState>>::IS_FROM_RESPONSE_BODY,
+                                    <#ty as IsFromResponseParts<__WiniState>>::IS_FROM_RESPONSE_PARTS,
+                                    <#ty as IsFromRequestParts<__WiniState>>::IS_FROM_REQUEST_PARTS,
                                 )
                             {
                                 (true, false,  false) => {
@@ -309,9 +309,9 @@ pub fn layout(args: TokenStream, item: TokenStream) -> TokenStream {
                         let dummy: #ty = #response_attr_condition else {
                             match
                                 (
-                                    <#ty as IsFromResponseBody>::IS_FROM_RESPONSE_BODY,
-                                    <#ty as IsFromResponseParts>::IS_FROM_RESPONSE_PARTS,
-                                    <#ty as IsFromRequestParts>::IS_FROM_REQUEST_PARTS,
+                                    <#ty as IsFromResponseBody<__WiniState>>::IS_FROM_RESPONSE_BODY,
+                                    <#ty as IsFromResponseParts<__WiniState>>::IS_FROM_RESPONSE_PARTS,
+                                    <#ty as IsFromRequestParts<__WiniState>>::IS_FROM_REQUEST_PARTS,
                                 )
                             {
                                 (true, false,  false) => {
@@ -334,11 +334,11 @@ pub fn layout(args: TokenStream, item: TokenStream) -> TokenStream {
         }
     }
 
-    let files_in_current_dir = get_js_or_css_files_in_current_dir();
-    let len_files_in_current_dir = files_in_current_dir.len();
+    let files_in_current_dir = linked_files::files_in_current_dir(&attributes.files);
+    let linked_files = linked_files::handle(&attributes.files, quote!(files), false);
     let meta_extensions = attributes.generate_all_extensions(true);
 
-    let js_pkgs = js_pkgs::handle(attributes.js_pkgs, quote!(files), true);
+    let js_pkgs = js_pkgs::handle(attributes.js_pkgs, quote!(files), false);
 
     // Generate the output code
     let expanded = quote! {
@@ -347,30 +347,49 @@ pub fn layout(args: TokenStream, item: TokenStream) -> TokenStream {
 
 
         #[allow(non_snake_case)]
-        pub async fn #name(
+        pub async fn #name<__WiniState: Clone + Send + Sync + 'static>(
+            axum::extract::State(__wini_state): axum::extract::State<__WiniState>,
             mut req: axum::extract::Request,
             next: axum::middleware::Next
         ) -> crate::shared::wini::err::ServerResult<axum::response::Response> {
             use {
                 axum::response::IntoResponse,
                 itertools::Itertools,
-                std::borrow::Cow,
             };
 
-            const FILES_IN_CURRENT_DIR: [Cow<'static, str>; #len_files_in_current_dir] = [#(Cow::Borrowed(#files_in_current_dir)),*];
+            const FILES_IN_CURRENT_DIR: &[&str] = &[#(#files_in_current_dir),*];
 
             #(#handling_of_request)*
 
+            let is_fragment_request = crate::shared::wini::layer::is_fragment_request(req.headers());
+
             let mut resp = next.run(req).await;
+
+            // Layouts only apply to full pages
+            if !crate::shared::wini::layer::is_full_page(&resp, is_fragment_request) {
+                return Ok(resp);
+            }
+
             let (mut resp_parts, resp_body) = resp.into_parts();
-            let html = #new_name( #(#handling_of_response),* ).await #early_return_if_is_result_err;
+            let render_start = std::time::Instant::now();
+
+            let html = #new_name( #(#handling_of_response),* ).await;
+
+            crate::shared::wini::metrics::RENDER_DURATION_SECONDS.observe(
+                &["layout", concat!(module_path!(), "::", stringify!(#name))],
+                render_start.elapsed(),
+            );
+
+            let html = html #early_return_if_is_result_err;
 
             let files: &mut crate::shared::wini::layer::Files = resp_parts
                 .extensions
                 .get_or_insert_default();
 
-            files.extend(html.linked_files.into_iter().map(Cow::Owned));
-            files.extend(FILES_IN_CURRENT_DIR);
+            files.extend(html.linked_files.iter().map(|file| crate::shared::wini::linked_file::LinkedFile::decode(file)));
+            files.extend(FILES_IN_CURRENT_DIR.iter().map(|file| crate::shared::wini::linked_file::LinkedFile::new(*file)));
+
+            #linked_files
 
             #js_pkgs
 
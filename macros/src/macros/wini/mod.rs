@@ -1,3 +1,4 @@
+pub mod action;
 pub mod args;
 pub mod cache;
 pub mod component;
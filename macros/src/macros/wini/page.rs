@@ -1,8 +1,9 @@
 use {
     super::args::ProcMacroParameters,
     crate::utils::wini::{
-        files::{get_current_file_path, get_js_or_css_files_in_current_dir},
+        files::get_current_file_path,
         js_pkgs,
+        linked_files,
         params_from_itemfn::params_from_itemfn,
         result::is_ouput_ty_result,
     },
@@ -18,6 +19,10 @@ pub fn page(args: TokenStream, item: TokenStream) -> TokenStream {
     let attr_parser = syn::meta::parser(|meta| attributes.parse(meta));
     parse_macro_input!(args with attr_parser);
 
+    if attributes.defer {
+        panic!("Only components can be deferred, with `#[component(defer)]`");
+    }
+
 
     // Modify the name of the original function to a reserved one
     let mut original_function = parse_macro_input!(item as syn::ItemFn);
@@ -33,18 +38,23 @@ pub fn page(args: TokenStream, item: TokenStream) -> TokenStream {
 
     let (arguments, param_names) = params_from_itemfn(&original_function);
 
-    let files_in_current_dir = get_js_or_css_files_in_current_dir();
-    let len_files_in_current_dir = files_in_current_dir.len();
+    let files_in_current_dir = linked_files::files_in_current_dir(&attributes.files);
+    let linked_files = linked_files::handle(&attributes.files, quote!(files), false);
     let meta_headers = attributes.generate_all_extensions(false);
-    let js_pkgs = js_pkgs::handle(attributes.js_pkgs, quote!(files), true);
+    let js_pkgs = js_pkgs::handle(attributes.js_pkgs, quote!(files), false);
+
+    let observe_render_duration = quote!(
+        crate::shared::wini::metrics::RENDER_DURATION_SECONDS.observe(
+            &["page", concat!(module_path!(), "::", stringify!(#original_name))],
+            render_start.elapsed(),
+        );
+    );
 
     let call_inner_page = if is_ouput_ty_result(&original_function) {
         quote!(
             match #new_name(#(#param_names),*).await {
                 Ok(resp) => resp,
                 Err(err) => {
-                    let mut resp = (&err).into_response();
-
                     let mut backtrace = crate::shared::wini::err::Backtrace::from(err);
                     backtrace.trace.push(
                         crate::shared::wini::err::Trace {
@@ -53,8 +63,11 @@ pub fn page(args: TokenStream, item: TokenStream) -> TokenStream {
                         }
                     );
 
+                    let mut resp = (&backtrace).into_response();
                     resp.extensions_mut().insert(backtrace);
 
+                    #observe_render_duration
+
                     return resp
                 }
             }
@@ -74,21 +87,25 @@ pub fn page(args: TokenStream, item: TokenStream) -> TokenStream {
             use {
                 axum::response::{IntoResponse, Html},
                 itertools::Itertools,
-                std::borrow::Cow,
             };
 
-            const FILES_IN_CURRENT_DIR: [Cow<'static, str>; #len_files_in_current_dir] = [#(Cow::Borrowed(#files_in_current_dir)),*];
+            const FILES_IN_CURRENT_DIR: &[&str] = &[#(#files_in_current_dir),*];
+
+            let render_start = std::time::Instant::now();
 
             let html = #call_inner_page;
 
-            let linked_files = html.linked_files.into_iter().map(Cow::Owned);
+            #observe_render_duration
+
 
             let mut resp = axum::response::IntoResponse::into_response(Html(html.content.0));
 
             let files: &mut crate::shared::wini::layer::Files = resp.extensions_mut().get_or_insert_default();
 
-            files.extend(FILES_IN_CURRENT_DIR);
-            files.extend(linked_files);
+            files.extend(FILES_IN_CURRENT_DIR.iter().map(|file| crate::shared::wini::linked_file::LinkedFile::new(*file)));
+            files.extend(html.linked_files.iter().map(|file| crate::shared::wini::linked_file::LinkedFile::decode(file)));
+
+            #linked_files
 
             #js_pkgs
 
@@ -1,24 +1,23 @@
 use {proc_macro2::TokenStream, quote::quote};
 
+/// Add the files of the packages to `add_to`: a `Files`, or the `linked_files` of a `Markup` if
+/// `encode`
 pub(crate) fn handle(
     js_pkgs: Option<Vec<String>>,
     add_to: TokenStream,
-    wrap_in_cow: bool,
+    encode: bool,
 ) -> TokenStream {
-    let package_stripped = if wrap_in_cow {
-        quote!(Cow::Owned(pkg.strip_prefix('/').unwrap_or(pkg).to_owned()))
+    let linked_file = if encode {
+        quote!(file.encode())
     } else {
-        quote!(pkg.strip_prefix('/').unwrap_or(pkg).to_owned())
+        quote!(file.clone())
     };
 
     if let Some(js_pkgs) = js_pkgs {
         quote!(#(
             match crate::shared::wini::packages_files::PACKAGES_FILES.get(#js_pkgs) {
-                Some(crate::shared::wini::packages_files::VecOrString::Vec(pkgs)) => {
-                    #add_to.extend(pkgs.into_iter().map(|pkg| #package_stripped));
-                },
-                Some(crate::shared::wini::packages_files::VecOrString::String(pkg)) => {
-                    #add_to.insert(#package_stripped);
+                Some(package_files) => {
+                    #add_to.extend(package_files.iter().map(|file| #linked_file));
                 },
                 None => panic!("Package `{}` does not exist", #js_pkgs),
             };
@@ -0,0 +1,139 @@
+use {
+    super::files::{get_current_file_path, get_js_or_css_files_in_current_dir},
+    proc_macro2::TokenStream,
+    quote::{format_ident, quote},
+    syn::{LitStr, meta::ParseNestedMeta},
+};
+
+/// The options of a file, with the values they accept (`None` for any string)
+const OPTIONS: [(&str, Option<&[&str]>); 7] = [
+    (
+        "kind",
+        Some(&[
+            "script",
+            "module",
+            "stylesheet",
+            "preload",
+            "font",
+        ]),
+    ),
+    ("loading", Some(&["defer", "async", "blocking"])),
+    ("media", None),
+    ("crossorigin", Some(&["anonymous", "use-credentials"])),
+    ("integrity", None),
+    ("priority", Some(&["high", "low", "auto"])),
+    ("placement", Some(&["head", "body"])),
+];
+
+/// A file linked with `file(url = "...", media = "print", ...)`
+#[derive(Default, Debug)]
+pub struct FileAttribute {
+    /// The path of the file from the root of the project if it was relative to the current file
+    /// (`./print.css`), or else its URL
+    pub url: String,
+    pub options: Vec<(String, String)>,
+}
+
+impl FileAttribute {
+    /// Parse the content of `file(...)`
+    pub fn parse(&mut self, meta: ParseNestedMeta) -> syn::Result<()> {
+        let Some(ident) = meta.path.get_ident().map(ToString::to_string) else {
+            return Err(meta.error("Expected an ident."));
+        };
+        let value = meta.value()?.parse::<LitStr>()?.value();
+
+        if ident == "url" {
+            self.url = match value.strip_prefix("./") {
+                Some(relative_path) => {
+                    get_current_file_path()
+                        .and_then(|path| path.parent().map(|dir| dir.join(relative_path)))
+                        .map_or(value.clone(), |path| path.to_string_lossy().into_owned())
+                },
+                None => value,
+            };
+            return Ok(());
+        }
+
+        match OPTIONS.iter().find(|(name, _)| *name == ident) {
+            Some((_, Some(values))) if !values.contains(&value.as_str()) => {
+                Err(meta.error(format!(
+                    "Unexpected value for `{ident}`: `{value}`. Expected one of: {}",
+                    values.join(", ")
+                )))
+            },
+            Some(_) => {
+                self.options.push((ident, value));
+                Ok(())
+            },
+            None => Err(meta.error(format!("Unexpected option of a file: {ident}"))),
+        }
+    }
+
+    /// The `LinkedFile` of the file
+    fn linked_file(&self) -> TokenStream {
+        let url = &self.url;
+        let options = self.options.iter().map(|(name, value)| {
+            let field = format_ident!("{name}");
+
+            if matches!(name.as_str(), "media" | "integrity") {
+                return quote!(#field: Some(String::from(#value)));
+            }
+
+            let ty = match name.as_str() {
+                "kind" => format_ident!("FileKind"),
+                "crossorigin" => format_ident!("CrossOrigin"),
+                other => format_ident!("{}", pascal_case(other)),
+            };
+            let variant = format_ident!("{}", pascal_case(value));
+
+            match name.as_str() {
+                "loading" | "placement" => {
+                    quote!(#field: crate::shared::wini::linked_file::#ty::#variant)
+                },
+                _ => quote!(#field: Some(crate::shared::wini::linked_file::#ty::#variant)),
+            }
+        });
+
+        quote!(crate::shared::wini::linked_file::LinkedFile {
+            #(#options,)*
+            ..crate::shared::wini::linked_file::LinkedFile::new(#url)
+        })
+    }
+}
+
+/// Add the files linked with `file(...)` to `add_to`: a `Files`, or the `linked_files` of a
+/// `Markup` if `encode`
+pub(crate) fn handle(files: &[FileAttribute], add_to: TokenStream, encode: bool) -> TokenStream {
+    let linked_files = files.iter().map(|file| {
+        let linked_file = file.linked_file();
+        if encode {
+            quote!(#add_to.insert(#linked_file.encode());)
+        } else {
+            quote!(#add_to.insert(#linked_file);)
+        }
+    });
+
+    quote!(#(#linked_files)*)
+}
+
+/// The javascript and css files in the directory of the proc_macro, except the ones linked with
+/// `file(...)`
+pub fn files_in_current_dir(files: &[FileAttribute]) -> Vec<String> {
+    get_js_or_css_files_in_current_dir()
+        .into_iter()
+        .filter(|path| !files.iter().any(|file| file.url == *path))
+        .collect()
+}
+
+fn pascal_case(value: &str) -> String {
+    value
+        .split('-')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
@@ -1,5 +1,6 @@
 pub mod files;
 pub mod js_pkgs;
+pub mod linked_files;
 pub mod params_from_itemfn;
 pub mod path;
 pub mod result;
@@ -67,9 +67,18 @@ info "Created '\e[1m$path\e[0m'."
 while [ "$relative_path" != "$src_directory_of_kind_new" ]; do
     basename="$(basename "$relative_path")"
     relative_path="$(dirname "$relative_path")"
+
+    # `[param]` and `[...param]` directories can't be the name of a module
+    if [[ "$basename" == \[*\] ]]; then
+        module_name="${basename//[\[\].]/}"
+        declaration="#[path = \"$basename/mod.rs\"]\npub mod $module_name;"
+    else
+        declaration="pub mod $basename;"
+    fi
+
     if [ -e "$relative_path/mod.rs" ]; then
-        echo "pub mod $basename;" >> "$relative_path/mod.rs"
+        echo -e "$declaration" >> "$relative_path/mod.rs"
     else
-        echo "pub mod $basename;" > "$relative_path/mod.rs"
+        echo -e "$declaration" > "$relative_path/mod.rs"
     fi
 done
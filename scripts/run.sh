@@ -18,4 +18,5 @@ trap on_interrupt SIGINT TERM EXIT
 
 watchexec -i "node_modules/**" -e 'ts' -r just compile-ts &
 watchexec -i "node_modules/**" -e 'scss' -r just compile-scss &
-watchexec -i "target/**" -i "node_modules/**" --stop-signal SIGTERM  -r "cargo run"
+# The other files are reloaded by the server itself, see `src/utils/wini/live_reload.rs`
+watchexec -i "target/**" -i "node_modules/**" -e 'rs,toml,ron' --stop-signal SIGTERM  -r "cargo run"
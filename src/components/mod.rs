@@ -1 +1,2 @@
 pub mod notfound;
+pub mod status;
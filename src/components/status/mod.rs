@@ -0,0 +1,18 @@
+use {
+    hyper::StatusCode,
+    maud::{Markup, html},
+    wini_macros::component,
+};
+
+/// The page of an error status code, such as `405 Method Not Allowed`
+#[component]
+pub async fn render(status: StatusCode) -> Markup {
+    html! {
+        main.status-page {
+            h1 { (status) }
+            a href=(url_for!(pages::doc::page::render, page = "introduction")) {
+                "Go to main page"
+            }
+        }
+    }
+}
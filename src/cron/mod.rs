@@ -3,8 +3,19 @@
 macro_rules! spawn_cron {
     ($fn_to_exec:expr, $interval:expr) => {
         tokio::spawn(async move {
+            use $crate::{
+                shared::wini::metrics::{CRON_FAILURES_TOTAL, CRON_RUNS_TOTAL},
+                utils::wini::cron::CronOutcome,
+            };
+
             loop {
-                $fn_to_exec().await;
+                let outcome = $fn_to_exec().await;
+
+                CRON_RUNS_TOTAL.inc(&[stringify!($fn_to_exec)]);
+                if outcome.is_failure() {
+                    CRON_FAILURES_TOTAL.inc(&[stringify!($fn_to_exec)]);
+                }
+
                 tokio::time::sleep($interval).await;
             }
         })
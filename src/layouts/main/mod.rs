@@ -1,21 +1,28 @@
 use {
-    crate::pages::doc::PAGES_STRUCTURE,
+    crate::{pages::doc::PAGES_STRUCTURE, shared::wini::alpine::XData},
     axum::http::uri::Uri,
     maud::{html, Markup, PreEscaped},
+    serde::Serialize,
     wini_macros::layout,
 };
 
+/// The data of Alpine shared by the sidebar and the pages
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Data<'l> {
+    is_sidebar_hidden: bool,
+    /// The page of the documentation that is shown
+    page: &'l str,
+}
+
 #[layout(js_pkgs = ["alpinejs", "htmx.org"])]
 pub async fn render(uri: Uri, child: Markup) -> Markup {
     html! {
         div
-            x-data={"{\
-                isSidebarHidden: false,\
-                liClick: () => {\
-                    if (window.innerWidth < 1200) $data.isSidebarHidden = true;\
-                },\
-                page: '"(uri.path().split('/').last().unwrap_or_default())"',\
-            }"}
+            x-data=(XData(Data {
+                is_sidebar_hidden: false,
+                page: uri.path().split('/').next_back().unwrap_or_default(),
+            }))
         {
             nav
                 #sidebar
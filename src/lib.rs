@@ -2,6 +2,11 @@
 #![feature(specialization)]
 #![feature(never_type)]
 #![feature(impl_trait_in_fn_trait_return)]
+
+// `url_for!`, generated by `build.rs` from the content of `src/pages`. Included before the modules
+// so that it can be used in all of them.
+include!(concat!(env!("OUT_DIR"), "/url_for.rs"));
+
 pub mod components;
 pub mod cron;
 pub mod layouts;
@@ -8,10 +8,18 @@ use {
             ENV_TYPE,
             JS_FILES,
             PUBLIC_ENDPOINTS,
+            assets::MANIFEST,
+            bundle::BUNDLES,
             components_files::COMPONENTS_FILES,
             config::SERVER_CONFIG,
             dependencies::SCRIPTS_DEPENDENCIES,
+            health,
+            import_map::IMPORT_MAP,
+            integrity,
             packages_files::PACKAGES_FILES,
+            precompressed::PRECOMPRESSED,
+            reloadable::Reloadable,
+            session,
             tsconfig::TSCONFIG_PATHS,
         },
     },
@@ -23,21 +31,41 @@ async fn main() {
     // Init color syntaxing
     colog::init();
 
+    // `verify-integrity` checks the pinned integrity of the packages, instead of starting the server
+    if std::env::args().nth(1).as_deref() == Some("verify-integrity") {
+        std::process::exit(if integrity::verify_packages() { 0 } else { 1 });
+    }
+
+    // Serve the probes as soon as possible, so that the server can be seen as initializing
+    let server = server::start().await;
+
     // Lock all the environment data that we will use in our application so it's not 'uninit'
     LazyLock::force(&ENV_TYPE);
-    LazyLock::force(&CSS_FILES);
-    LazyLock::force(&JS_FILES);
+    Reloadable::force(&CSS_FILES);
+    Reloadable::force(&JS_FILES);
     LazyLock::force(&PACKAGES_FILES);
+    Reloadable::force(&MANIFEST);
+    Reloadable::force(&PRECOMPRESSED);
+    Reloadable::force(&BUNDLES);
     LazyLock::force(&TSCONFIG_PATHS);
-    LazyLock::force(&PUBLIC_ENDPOINTS);
-    LazyLock::force(&SCRIPTS_DEPENDENCIES);
+    Reloadable::force(&PUBLIC_ENDPOINTS);
+    Reloadable::force(&SCRIPTS_DEPENDENCIES);
+    Reloadable::force(&IMPORT_MAP);
     LazyLock::force(&COMPONENTS_FILES);
     LazyLock::force(&SERVER_CONFIG);
 
     // Verify that all the kind of data returned by the server (html, css, js, etc.) have their
     // cache rules being correctly setup
     SERVER_CONFIG.cache.verify_all_attributes();
+    SERVER_CONFIG.security.verify_all_attributes();
+
+    // Exit now if the sessions can't be signed or stored
+    session::init();
 
     cron::launch_crons().await;
-    server::start().await;
+
+    // Everything is initialized (`#[init_cache]` functions are warmed up before `main`)
+    health::mark_as_ready();
+
+    server.await.expect("The server stopped.");
 }
@@ -0,0 +1,14 @@
+use {
+    crate::shared::wini::err::ServerResult,
+    axum::extract::Path,
+    maud::{Markup, html},
+    wini_macros::page,
+};
+
+/// A page of the documentation
+#[page]
+pub async fn render(page: Path<String>) -> ServerResult<Markup> {
+    Ok(html! {
+        [super::content(page.0)?]
+    })
+}
@@ -1,16 +1,24 @@
 use {
-    crate::components::notfound,
-    axum::extract::Request,
+    crate::shared::wini::{alpine::XData, err::ServerResult, htmx, reloadable::Reloadable},
     font_awesome_as_a_crate::{svg, Type},
+    hyper::StatusCode,
     itertools::Itertools,
     maud::{html, Markup, PreEscaped},
     pulldown_cmark::Options,
     std::{collections::HashMap, sync::LazyLock},
-    wini_macros::page,
+    wini_macros::{component, page},
 };
 
+#[path = "[...page]/mod.rs"]
+pub mod page;
 mod style_code;
 
+/// The element of the main layout in which the pages are swapped by htmx
+const CONTENT_TARGET: &str = "#horizontal-content";
+
+/// Run when a page is chosen in the sidebar: on small screens, it hides the page
+const HIDE_SIDEBAR_ON_SMALL_SCREENS: &str = "if (window.innerWidth < 1200) isSidebarHidden = true";
+
 // static MARKDOWN_PAGES: LazyLock<HashMap<String, String>> = LazyLock::new(|| Vec::new());
 
 
@@ -56,12 +64,11 @@ impl<'l> PageOrDirectory<'l> {
             PageOrDirectory::Page { title, page } => {
                 html! {
                     li.cursor
-                        hx-get={"/htmx/" (page)}
-                        hx-target="#horizontal-content"
-                        hx-replace-url={"/doc/" (page)}
-                        x-on:click={"page = '"(page)"'; liClick()"}
-                        x-bind:class={"'"(page)"' == page && 'active'"}
-                    { (title) }
+                        x-on:click={"page = " (XData(page)) "; " (HIDE_SIDEBAR_ON_SMALL_SCREENS)}
+                        x-bind:class={(XData(page)) " == page && 'active'"}
+                    {
+                        (htmx::link(&url_for!(pages::doc::page::render, page = page), CONTENT_TARGET, title))
+                    }
                 }
             },
             PageOrDirectory::Directory {
@@ -70,12 +77,11 @@ impl<'l> PageOrDirectory<'l> {
                 html! {
                     @if let Some(page) = page {
                         li.cursor
-                            hx-get={"/htmx/" (page)}
-                            hx-target="#horizontal-content"
-                            hx-replace-url={"/doc/" (page)}
-                            x-on:click={"page = '"(page)"'; liClick()"}
-                            x-bind:class={"'"(page)"' == page && 'active'"}
-                        { (title) }
+                            x-on:click={"page = " (XData(page)) "; " (HIDE_SIDEBAR_ON_SMALL_SCREENS)}
+                            x-bind:class={(XData(page)) " == page && 'active'"}
+                        {
+                            (htmx::link(&url_for!(pages::doc::page::render, page = page), CONTENT_TARGET, title))
+                        }
                     } @else {
                         li { (title) }
                     }
@@ -129,10 +135,10 @@ fn search_file_recursively(dir: &str, target_name: &str) -> std::io::Result<Opti
 }
 
 
-pub static PAGES: LazyLock<HashMap<String, String>> = LazyLock::new(pages);
+pub static PAGES: Reloadable<HashMap<String, String>> = Reloadable::new(pages);
 
 /// # Panics
-/// Since this code will only be called on a LazyLock, it's ok if it panics
+/// Since this code will only be called on a LazyLock or a Reloadable, it's ok if it panics
 pub fn pages() -> HashMap<String, String> {
     let page_structure: PageOrDirectory = ron::from_str(&include_str!("./structure.ron")).unwrap();
 
@@ -206,29 +212,36 @@ pub static PAGES_STRUCTURE: LazyLock<PageOrDirectory> =
 
 
 
+/// The documentation's index: its introduction
 #[page]
-pub async fn render(req: Request) -> Markup {
-    let requested_page = req
-        .uri()
-        .path()
-        .split('/')
-        .skip(2)
-        .next()
-        .unwrap_or("introduction");
-
-    let Some(result) = PAGES.get(requested_page) else {
-        return html! { [notfound::render] };
+pub async fn render() -> ServerResult<Markup> {
+    Ok(html! {
+        [content("introduction".to_owned())?]
+    })
+}
+
+/// The content of a page of the documentation, with the buttons to go to the previous and next
+/// pages
+#[component]
+pub async fn content(requested_page: String) -> ServerResult<Markup> {
+    let requested_page = requested_page.as_str();
+
+    let pages = PAGES.load();
+    let Some(result) = pages.get(requested_page) else {
+        return Err(StatusCode::NOT_FOUND.into());
     };
 
     let (previous_page, next_page) = PAGES_STRUCTURE.get_nearest_pages(requested_page);
 
-    html! {
+    Ok(html! {
         @if let Some(previous_page) = previous_page {
-            button.previous-next
-                hx-get={"/htmx/" (previous_page)}
-                hx-target="#horizontal-content"
-                hx-replace-url={"/doc/" (previous_page)}
-                x-on:click={"page = '" (previous_page)"'"}
+            @let url = url_for!(pages::doc::page::render, page = previous_page);
+            a.previous-next
+                href=(url)
+                hx-get=(url)
+                hx-target=(CONTENT_TARGET)
+                hx-push-url="true"
+                x-on:click={"page = " (XData(&previous_page))}
             {
                 (PreEscaped(
                     svg(Type::Solid, "angle-left").unwrap()
@@ -243,11 +256,13 @@ pub async fn render(req: Request) -> Markup {
             }
         }
         @if let Some(next_page) = next_page {
-            button.previous-next
-                hx-get={"/htmx/" (next_page)}
-                hx-replace-url={"/doc/" (next_page)}
-                hx-target="#horizontal-content"
-                x-on:click={"page = '" (next_page)"'"}
+            @let url = url_for!(pages::doc::page::render, page = next_page);
+            a.previous-next
+                href=(url)
+                hx-get=(url)
+                hx-target=(CONTENT_TARGET)
+                hx-push-url="true"
+                x-on:click={"page = " (XData(&next_page))}
             {
                 (PreEscaped(
                     svg(
@@ -260,5 +275,5 @@ pub async fn render(req: Request) -> Markup {
         } @else {
             .placeholder-previous-next {}
         }
-    }
+    })
 }
@@ -1,43 +1,146 @@
 use {
     crate::{
+        components::{notfound, status},
         layouts::main,
         pages,
-        shared::wini::{layer::MetaLayerBuilder, PORT},
+        shared::{
+            app_state::AppState,
+            wini::{
+                ADMIN_PORT,
+                ENV_TYPE,
+                PORT,
+                env::EnvType,
+                precompressed::NotForStaticFiles,
+                streaming::NotForStreamedPages,
+            },
+        },
         template,
         utils::wini::{
+            admin,
             cache,
             handling_file::{self},
+            live_reload,
+            security,
+            session,
+            status_pages::{self, StatusPage},
         },
     },
-    axum::{middleware, response::Redirect, routing::get, Router},
+    axum::{Router, middleware, response::Redirect, routing::get},
+    hyper::StatusCode,
     log::info,
-    std::collections::HashMap,
-    tower_http::compression::CompressionLayer,
+    tokio::task::JoinHandle,
+    tower_http::compression::{CompressionLayer, DefaultPredicate, Predicate},
 };
 
 
-pub async fn start() {
+// Generated by `build.rs` from the content of `src/pages`
+include!(concat!(env!("OUT_DIR"), "/pages_router.rs"));
+
+
+/// Start serving the application, and return the task that serves it.
+///
+/// This should be called before the initialization of the server, so that its readiness can be
+/// probed while it's initializing: `/metrics`, `/healthz` and `/readyz` are served on `ADMIN_PORT`
+/// if it's specified, or else on `PORT` with the rest of the application, whose requests get a
+/// `503 Service Unavailable` until the server is ready.
+pub async fn start() -> JoinHandle<()> {
+    status_pages::set_status_pages([
+        (
+            StatusCode::NOT_FOUND,
+            (|| Box::pin(notfound::render())) as StatusPage,
+        ),
+        (StatusCode::METHOD_NOT_ALLOWED, || {
+            Box::pin(status::render(StatusCode::METHOD_NOT_ALLOWED))
+        }),
+        (StatusCode::INTERNAL_SERVER_ERROR, || {
+            Box::pin(status::render(StatusCode::INTERNAL_SERVER_ERROR))
+        }),
+        (StatusCode::SERVICE_UNAVAILABLE, || {
+            Box::pin(status::render(StatusCode::SERVICE_UNAVAILABLE))
+        }),
+    ]);
+
+    // Only in the `Local` environment
+    live_reload::watch(&[("md", || pages::doc::PAGES.reload())]);
+
+    let app = router();
+
+    // If there is no dedicated port for them, the admin endpoints are served with the rest of the
+    // application
+    let app = match *ADMIN_PORT {
+        Some(admin_port) => {
+            serve(admin_port, admin::router()).await;
+            app
+        },
+        None => app.merge(admin::router()),
+    };
+
+    serve(*PORT, app).await
+}
+
+
+/// The router of the application, with all its layers, but without the admin endpoints
+pub fn router() -> Router {
+    let state = AppState;
+
     // The main router of the application is defined here
     let app = Router::new()
-        .route("/doc/{*wildcard}", get(pages::doc::render))
-        .route("/doc", get(pages::doc::render))
-        .layer(middleware::from_fn(main::render))
+        .merge(pages_router(state.clone()))
+        .fallback(status_pages::fallback)
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            main::render::<AppState>,
+        ))
         .layer(middleware::from_fn(template::template))
         .layer(middleware::from_fn(cache::html_middleware))
-        .route("/htmx/{segment}", get(pages::doc::render))
-        .route("/{*wildcard}", get(handling_file::handle_file))
-        .route("/", get(Redirect::permanent("/doc/introduction")))
-        .layer(CompressionLayer::new());
+        // The files are served before the pages, and the requests that don't match any file nor
+        // page get the page of `404 Not Found`
+        .layer(middleware::from_fn(handling_file::handle_file))
+        .route(
+            "/",
+            get(Redirect::permanent(&url_for!(
+                pages::doc::page::render,
+                page = "introduction"
+            ))),
+        )
+        .layer(middleware::from_fn(session::csrf_protection))
+        .layer(middleware::from_fn(session::session_layer))
+        // The static files are already compressed, and the compression would hold the streamed
+        // pages back until they're complete
+        .layer(
+            CompressionLayer::new().compress_when(
+                DefaultPredicate::new()
+                    .and(NotForStaticFiles)
+                    .and(NotForStreamedPages),
+            ),
+        )
+        // Outside of the compression, so that the bodies of the `304 Not Modified` are dropped
+        // before being compressed
+        .layer(middleware::from_fn(cache::conditional_requests))
+        .layer(middleware::from_fn(security::security_headers))
+        .layer(middleware::from_fn(admin::track_requests))
+        .with_state(state);
+
+    if *ENV_TYPE == EnvType::Local {
+        app.merge(live_reload::router())
+    } else {
+        app
+    }
+    .layer(middleware::from_fn(admin::reject_until_ready))
+}
 
 
-    // Start the server
-    info!("Starting listening on port {}...", *PORT);
-    let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", *PORT))
+/// Start listening on `port`, and serve `router` in the background
+async fn serve(port: u16, router: Router) -> JoinHandle<()> {
+    info!("Starting listening on port {port}...");
+    let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{port}"))
         .await
         .expect("Couldn't start the TcpListener of the specified port.");
 
     info!("Starting the server...");
-    axum::serve(listener, app)
-        .await
-        .expect("Couldn't start the server.");
+    tokio::spawn(async move {
+        axum::serve(listener, router)
+            .await
+            .expect("Couldn't start the server.");
+    })
 }
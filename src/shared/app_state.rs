@@ -0,0 +1,5 @@
+/// The state shared by all the pages and layouts, available with `State<AppState>`.
+///
+/// Add what they need to it: a pool of connections to a database, a handle to a configuration...
+#[derive(Debug, Clone, Default)]
+pub struct AppState;
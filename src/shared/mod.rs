@@ -1,2 +1,4 @@
+/// The state of the router
+pub mod app_state;
 /// Core shared for wini
 pub mod wini;
@@ -0,0 +1,220 @@
+//! Types used by `#[action]`s: the handlers of the forms submitted to a page.
+
+use {
+    super::{
+        htmx::{HxRequest, HxResponse},
+        layer::Fragment,
+    },
+    axum::{
+        body::Body,
+        extract::{FromRequestParts, Request},
+        handler::Handler,
+        http::{Method, request::Parts},
+        response::{Html, IntoResponse, Redirect, Response},
+    },
+    hyper::StatusCode,
+    maud::Markup,
+    serde::de::DeserializeOwned,
+    std::{
+        collections::{BTreeMap, HashMap},
+        convert::Infallible,
+    },
+};
+
+
+/// The maximum size of a submitted form
+const MAX_FORM_SIZE: usize = 2 * 1024 * 1024;
+
+/// The errors of the fields of a form, by name of field
+#[derive(Debug, Clone, Default)]
+pub struct FieldErrors(BTreeMap<String, String>);
+
+impl FieldErrors {
+    /// The field used for the errors that concern the whole form, such as a form that can't be
+    /// deserialized
+    pub const FORM: &str = "_form";
+
+    pub fn add<F: Into<String>, M: Into<String>>(&mut self, field: F, message: M) {
+        self.0.insert(field.into(), message.into());
+    }
+
+    pub fn get(&self, field: &str) -> Option<&str> {
+        self.0.get(field).map(String::as_str)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<F: Into<String>, M: Into<String>> FromIterator<(F, M)> for FieldErrors {
+    fn from_iter<I: IntoIterator<Item = (F, M)>>(iter: I) -> Self {
+        Self(
+            iter.into_iter()
+                .map(|(field, message)| (field.into(), message.into()))
+                .collect(),
+        )
+    }
+}
+
+
+/// Validation of a form, once deserialized
+///
+/// # Example
+///
+/// ```ignore
+/// impl Validate for Subscription {
+///     fn validate(&self) -> FieldErrors {
+///         let mut errors = FieldErrors::default();
+///         if !self.email.contains('@') {
+///             errors.add("email", "This is not a valid email address.");
+///         }
+///         errors
+///     }
+/// }
+/// ```
+pub trait Validate {
+    fn validate(&self) -> FieldErrors {
+        FieldErrors::default()
+    }
+}
+
+
+/// What to respond once an action is done
+#[derive(Debug)]
+pub enum ActionResponse {
+    /// Redirect to another URL: with `HX-Redirect` for htmx requests, `303 See Other` otherwise
+    Redirect(String),
+    /// Send a fragment of HTML, without the layouts and the template. Useful for htmx.
+    Fragment(Markup),
+    /// Render the page again with these errors, and the values that were submitted
+    Invalid(FieldErrors),
+}
+
+
+/// The feedback of an invalid submission of a form, given to the page when it's rendered again.
+///
+/// When the page isn't rendered after a submission, there is no value and no error.
+///
+/// # Example
+///
+/// ```ignore
+/// #[page]
+/// pub async fn render(feedback: ActionFeedback) -> Markup {
+///     html! {
+///         form method="post" {
+///             input name="email" value=(feedback.value("email"));
+///             @if let Some(error) = feedback.error("email") {
+///                 p.error { (error) }
+///             }
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ActionFeedback {
+    /// The values submitted, by name of field
+    pub values: HashMap<String, String>,
+    pub errors: FieldErrors,
+}
+
+impl ActionFeedback {
+    /// The value submitted for this field, or an empty string
+    pub fn value(&self, field: &str) -> &str {
+        self.values.get(field).map_or("", String::as_str)
+    }
+
+    pub fn error(&self, field: &str) -> Option<&str> {
+        self.errors.get(field)
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ActionFeedback {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts.extensions.get::<Self>().cloned().unwrap_or_default())
+    }
+}
+
+
+/// A form submitted to an action
+pub struct Submission<T> {
+    /// The deserialized form, or why it couldn't be deserialized
+    pub form: Result<T, String>,
+    /// The raw values submitted
+    pub values: HashMap<String, String>,
+    /// The request, without its body
+    pub request: Request,
+}
+
+impl<T: DeserializeOwned> Submission<T> {
+    pub async fn from_request(req: Request) -> Self {
+        let (parts, body) = req.into_parts();
+
+        let (form, values) = match axum::body::to_bytes(body, MAX_FORM_SIZE).await {
+            Ok(bytes) => {
+                (
+                    serde_urlencoded::from_bytes::<T>(&bytes).map_err(|err| err.to_string()),
+                    form_urlencoded::parse(&bytes).into_owned().collect(),
+                )
+            },
+            Err(err) => (Err(err.to_string()), HashMap::new()),
+        };
+
+        Self {
+            form,
+            values,
+            request: Request::from_parts(parts, Body::empty()),
+        }
+    }
+}
+
+impl<T: Validate> Submission<T> {
+    /// The form, if it's valid
+    pub fn validated(&mut self) -> Result<T, FieldErrors> {
+        let form = std::mem::replace(&mut self.form, Err(String::new()))
+            .map_err(|err| FieldErrors::from_iter([(FieldErrors::FORM, err)]))?;
+
+        let errors = form.validate();
+        if errors.is_empty() {
+            Ok(form)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Convert the response of an action to a real response. If the form is invalid, `page` is
+    /// rendered again, with the state of the action.
+    pub async fn respond<H, P, S>(self, response: ActionResponse, page: H, state: S) -> Response
+    where
+        H: Handler<P, S>,
+    {
+        let is_htmx = HxRequest::from_headers(self.request.headers()).is_htmx;
+
+        let mut resp = match response {
+            ActionResponse::Redirect(url) if is_htmx => {
+                HxResponse::default().redirect(url).into_response()
+            },
+            ActionResponse::Redirect(url) => Redirect::to(&url).into_response(),
+            // The files linked to the fragment are not included, since there is no template
+            ActionResponse::Fragment(markup) => Html(markup.content.0).into_response(),
+            ActionResponse::Invalid(errors) => {
+                let (mut parts, body) = self.request.into_parts();
+                parts.method = Method::GET;
+                parts.extensions.insert(ActionFeedback {
+                    values: self.values,
+                    errors,
+                });
+
+                let mut resp = page.call(Request::from_parts(parts, body), state).await;
+                *resp.status_mut() = StatusCode::UNPROCESSABLE_ENTITY;
+
+                return resp;
+            },
+        };
+
+        resp.extensions_mut().insert(Fragment);
+        resp
+    }
+}
@@ -0,0 +1,48 @@
+//! Data given to Alpine.js from Rust
+
+use {
+    maud::{Escaper, Render},
+    serde::Serialize,
+    std::fmt::Write,
+};
+
+
+/// A value serialized as JSON, to be used as the data of Alpine in `x-data`, or as a value in the
+/// expressions of its other attributes.
+///
+/// Since JSON is a valid JavaScript expression, and the value is escaped like any other one in
+/// `html!`, strings can't break out of it.
+///
+/// # Example
+///
+/// ```ignore
+/// #[derive(Serialize)]
+/// #[serde(rename_all = "camelCase")]
+/// struct Menu<'l> {
+///     is_open: bool,
+///     page: &'l str,
+/// }
+///
+/// html! {
+///     nav x-data=(XData(Menu { is_open: false, page: uri.path() })) {
+///         a x-on:click={"page = " (XData("home"))} { "Home" }
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct XData<T>(pub T);
+
+impl<T: Serialize> Render for XData<T> {
+    fn render_to(&self, buffer: &mut String) {
+        match serde_json::to_string(&self.0) {
+            Ok(json) => {
+                // Writing to a `String` doesn't fail
+                let _ = Escaper::new(buffer).write_str(&json);
+            },
+            Err(err) => {
+                log::error!("Couldn't serialize the data of Alpine: {err}");
+                buffer.push_str("{}");
+            },
+        }
+    }
+}
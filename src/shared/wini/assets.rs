@@ -0,0 +1,162 @@
+//! The fingerprinted URLs of the CSS and JavaScript files, such as
+//! `/src/pages/doc/style.3f9a1c2b.css`: the hash of their content is in their URL, so that they
+//! can be cached forever. When a file changes, it gets a new URL.
+//!
+//! The validators of the files (`ETag` and `Last-Modified`) are computed at the same time, for the
+//! conditional requests, as well as their integrity.
+
+use {
+    super::{
+        CSS_FILES,
+        JS_FILES,
+        integrity,
+        packages_files::PACKAGES_FILES,
+        precompressed::Encoding,
+        reloadable::Reloadable,
+    },
+    sha2::{Digest, Sha256},
+    std::{collections::HashMap, fmt::Write, path::Path, time::SystemTime},
+};
+
+
+/// The number of bytes of the hash put in the URLs, as hexadecimal
+pub const HASH_LEN: usize = 4;
+
+/// The number of bytes of the hash put in the `ETag`s, as hexadecimal
+pub const ETAG_HASH_LEN: usize = 16;
+
+/// The fingerprinted URLs of the files of `src/` and of the packages
+pub static MANIFEST: Reloadable<Manifest> = Reloadable::new(Manifest::build);
+
+
+#[derive(Debug, Default)]
+pub struct Manifest {
+    /// The fingerprinted URL of each file, by URL
+    fingerprinted_urls: HashMap<String, String>,
+    /// The URL of each file, by fingerprinted URL
+    urls: HashMap<String, String>,
+    /// The validators of each file, by URL
+    validators: HashMap<String, Validators>,
+    /// The Subresource Integrity of each file, by URL
+    integrity: HashMap<String, String>,
+}
+
+/// What identifies the version of a file, to answer the conditional requests
+#[derive(Debug, Clone)]
+pub struct Validators {
+    /// A strong `ETag`, quoted
+    pub etag: String,
+    pub last_modified: Option<SystemTime>,
+}
+
+impl Validators {
+    /// The validators of a file: the hash of its content and the time its file was modified
+    pub fn new<P: AsRef<Path>>(content: &[u8], path: P) -> Self {
+        Self {
+            etag: format!("\"{}\"", hash(content, ETAG_HASH_LEN)),
+            last_modified: std::fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok(),
+        }
+    }
+
+    /// The validators of the file compressed with `encoding`, which is another representation of
+    /// it, with another `ETag`
+    pub fn for_encoding(&self, encoding: Encoding) -> Self {
+        Self {
+            etag: format!(
+                "{}-{}\"",
+                self.etag.trim_end_matches('"'),
+                encoding.as_str()
+            ),
+            last_modified: self.last_modified,
+        }
+    }
+}
+
+impl Manifest {
+    fn build() -> Self {
+        let mut manifest = Self::default();
+
+        for (url, content) in CSS_FILES.load().iter().chain(JS_FILES.load().iter()) {
+            // The URL is the path of the file from the root of the project
+            manifest.insert(url, content.as_bytes(), url.trim_start_matches('/'));
+        }
+
+        // The files of the packages are in the public directory
+        for url in PACKAGES_FILES.values().flatten().map(|file| &file.url) {
+            if url.contains("://") {
+                continue;
+            }
+
+            let path = format!("./public{url}");
+            match std::fs::read(&path) {
+                Ok(content) => manifest.insert(url, &content, &path),
+                Err(err) => log::warn!("Couldn't fingerprint the file of package `{url}`: {err}"),
+            }
+        }
+
+        manifest
+    }
+
+    /// Fingerprint a file, from its content and the path of its file, for its `Last-Modified`
+    pub fn insert(&mut self, url: &str, content: &[u8], path: &str) {
+        let fingerprinted_url = fingerprint(url, content);
+
+        self.validators
+            .insert(url.to_owned(), Validators::new(content, path));
+        self.integrity
+            .insert(url.to_owned(), integrity::integrity(content));
+        self.urls.insert(fingerprinted_url.clone(), url.to_owned());
+        self.fingerprinted_urls
+            .insert(url.to_owned(), fingerprinted_url);
+    }
+
+    /// The fingerprinted URL of a file, or `url` if it isn't fingerprinted
+    pub fn url<'l>(&'l self, url: &'l str) -> &'l str {
+        self.fingerprinted_urls.get(url).map_or(url, String::as_str)
+    }
+
+    /// The URL of the file of a fingerprinted URL
+    pub fn file(&self, fingerprinted_url: &str) -> Option<&str> {
+        self.urls.get(fingerprinted_url).map(String::as_str)
+    }
+
+    /// The validators of a file, by its URL
+    pub fn validators(&self, url: &str) -> Option<&Validators> {
+        self.validators.get(url)
+    }
+
+    /// The integrity of a file, by its URL
+    pub fn integrity(&self, url: &str) -> Option<&str> {
+        self.integrity.get(url).map(String::as_str)
+    }
+}
+
+
+/// The first `len` bytes of the SHA-256 of `content`, as hexadecimal
+pub fn hash(content: &[u8], len: usize) -> String {
+    Sha256::digest(content).iter().take(len).fold(
+        String::with_capacity(len * 2),
+        |mut hash, byte| {
+            let _ = write!(hash, "{byte:02x}");
+            hash
+        },
+    )
+}
+
+
+/// Put the hash of `content` before the extension of `url`
+pub fn fingerprint(url: &str, content: &[u8]) -> String {
+    let hash = hash(content, HASH_LEN);
+
+    match Path::new(url).extension().and_then(|ext| ext.to_str()) {
+        Some(extension) => {
+            format!(
+                "{}.{hash}.{extension}",
+                url.strip_suffix(&format!(".{extension}")).unwrap_or(url)
+            )
+        },
+        None => format!("{url}.{hash}"),
+    }
+}
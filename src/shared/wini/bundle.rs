@@ -0,0 +1,411 @@
+//! The bundles of the CSS and JavaScript files of the pages: when it's enabled in `wini.toml`, the
+//! files linked by a page are concatenated, in their order, into one file of each kind, so that
+//! they are downloaded with one request.
+//!
+//! The URL of a bundle is made of the sorted IDs of its files and of a hash of their fingerprinted
+//! URLs in their order, like `/__wini/bundle/3f9a1c2b/a1b2c3-d4e5f6.js`. The bundles are built on
+//! a blocking thread when a page that links them is rendered, and kept in memory until the files
+//! are loaded again: only these bundles are served, so that the requests can't make the server
+//! build others. They are minified in the `Prod` and `Staging` environments.
+//!
+//! The relative URLs of the style sheets are made absolute, since they are served from another
+//! directory. The style sheets with `@import` or `@namespace`, which must be at the start of the
+//! file, aren't bundled.
+
+use {
+    super::{
+        CSS_FILES,
+        ENV_TYPE,
+        JS_FILES,
+        assets::{self, ETAG_HASH_LEN, MANIFEST, Validators},
+        config::SERVER_CONFIG,
+        dependencies::normalize_relative_path,
+        env::EnvType,
+        integrity,
+        linked_file::LinkedFile,
+        packages_files::PACKAGES_FILES,
+        precompressed::Precompressed,
+        reloadable::Reloadable,
+    },
+    axum::body::Bytes,
+    itertools::Itertools,
+    lightningcss::{
+        rules::CssRule,
+        stylesheet::{MinifyOptions, ParserOptions, PrinterOptions, StyleSheet},
+        values::url::Url,
+        visit_types,
+        visitor::{Visit, VisitTypes, Visitor},
+    },
+    minify_js::{Session, TopLevelMode},
+    std::{
+        collections::HashMap,
+        convert::Infallible,
+        path::Path,
+        sync::{Arc, Mutex, MutexGuard, PoisonError},
+    },
+};
+
+
+/// The URL at which the bundles are served
+pub const ROUTE: &str = "/__wini/bundle/";
+
+/// The number of bytes of the hash of the URL of a file, as hexadecimal, in the URLs of the bundles
+const ID_LEN: usize = 3;
+
+/// The number of bytes of the hash of the fingerprinted URLs of the files of a bundle, as
+/// hexadecimal, in its URL
+const VERSION_LEN: usize = 4;
+
+/// The maximum number of bundles kept in memory. Once it's reached, the pages that would need
+/// other bundles link their files one by one.
+const MAX_BUNDLES: usize = 1024;
+
+/// The files that can be bundled, and the bundles built since they were loaded
+pub static BUNDLES: Reloadable<Bundles> = Reloadable::new(Bundles::build);
+
+
+#[derive(Debug, Default)]
+pub struct Bundles {
+    /// The ID and the fingerprinted URL of each file that can be bundled, by URL
+    files: HashMap<String, (String, String)>,
+    /// The bundles, by URL
+    bundles: Mutex<HashMap<String, Arc<Bundle>>>,
+}
+
+/// The files of a page concatenated into one
+#[derive(Debug)]
+pub struct Bundle {
+    pub content: Bytes,
+    pub content_type: &'static str,
+    pub validators: Validators,
+    pub precompressed: Precompressed,
+    pub integrity: String,
+}
+
+impl Bundles {
+    fn build() -> Self {
+        let manifest = MANIFEST.load();
+        let (css_files, js_files) = (CSS_FILES.load(), JS_FILES.load());
+
+        let packages_files = PACKAGES_FILES
+            .values()
+            .flatten()
+            .filter(|file| file.is_local())
+            .map(|file| &file.url)
+            .filter(|url| url.ends_with(".css") || url.ends_with(".js"));
+
+        let files = css_files
+            .keys()
+            .chain(js_files.keys())
+            .chain(packages_files)
+            .sorted()
+            .dedup()
+            .filter(|url| {
+                !url.ends_with(".css") || read(url).is_some_and(|css| can_bundle_css(url, &css))
+            })
+            .map(|url| (url.clone(), manifest.url(url).to_owned()));
+
+        Self::new(files)
+    }
+
+    /// The files that can be bundled, with their fingerprinted URL
+    pub fn new<I: IntoIterator<Item = (String, String)>>(files: I) -> Self {
+        let mut bundles = Self::default();
+        let mut urls = HashMap::new();
+
+        for (url, fingerprinted_url) in files {
+            let id = assets::hash(url.as_bytes(), ID_LEN);
+
+            if let Some(other) = urls.get(&id) {
+                log::warn!("`{url}` isn't bundled, since it has the same ID as `{other}`");
+                continue;
+            }
+
+            urls.insert(id.clone(), url.clone());
+            bundles.files.insert(url, (id, fingerprinted_url));
+        }
+
+        bundles
+    }
+
+    /// Whether the file can be in a bundle
+    pub fn can_bundle(&self, url: &str) -> bool {
+        self.files.contains_key(url)
+    }
+
+    /// The URL of the bundle of `files`, all of the same kind, in this order, if they can be
+    /// bundled. It's the same on each server with the same files.
+    pub fn url(&self, files: &[String]) -> Option<String> {
+        let files = files
+            .iter()
+            .map(|file| self.files.get(file))
+            .collect::<Option<Vec<_>>>()?;
+
+        // A file is only linked once
+        if !files.iter().map(|(id, _)| id).all_unique() {
+            return None;
+        }
+
+        let ids = files.iter().map(|(id, _)| id.as_str()).sorted().join("-");
+        let version = assets::hash(
+            files
+                .iter()
+                .map(|(_, fingerprinted_url)| fingerprinted_url.as_str())
+                .join("\n")
+                .as_bytes(),
+            VERSION_LEN,
+        );
+        let extension = if files.first()?.1.ends_with(".css") {
+            "css"
+        } else {
+            "js"
+        };
+
+        Some(format!("{ROUTE}{version}/{ids}.{extension}"))
+    }
+
+    /// The bundle at this URL, if it was built for a page
+    pub fn built(&self, url: &str) -> Option<Arc<Bundle>> {
+        self.bundles().get(url).cloned()
+    }
+
+    /// The URL of the bundle of `files`, built on a blocking thread if it isn't yet. `None` if
+    /// they can't be bundled, or if there are already too many bundles.
+    pub async fn register(self: Arc<Self>, files: Vec<String>) -> Option<String> {
+        let url = self.url(&files)?;
+        if self.built(&url).is_some() {
+            return Some(url);
+        }
+        if self.is_full() {
+            return None;
+        }
+
+        tokio::task::spawn_blocking(move || {
+            let bundle = Arc::new(Bundle::new(&url, &files)?);
+
+            // Other bundles may have been built in the meantime
+            let mut bundles = self.bundles();
+            if bundles.len() >= MAX_BUNDLES && !bundles.contains_key(&url) {
+                return None;
+            }
+            bundles.insert(url.clone(), bundle);
+            Some(url)
+        })
+        .await
+        .ok()
+        .flatten()
+    }
+
+    fn is_full(&self) -> bool {
+        let is_full = self.bundles().len() >= MAX_BUNDLES;
+        if is_full {
+            log::warn!(
+                "There are already {MAX_BUNDLES} bundles, the files of the page are linked one by one"
+            );
+        }
+        is_full
+    }
+
+    fn bundles(&self) -> MutexGuard<'_, HashMap<String, Arc<Bundle>>> {
+        self.bundles.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+
+/// Replace the files, all of the same kind, by their bundles if the bundling is enabled. The
+/// files of other websites, and the ones linked with options (such as `async` or `media`), stay
+/// where they are, between the bundles of the files before and after them.
+pub async fn bundle(files: Vec<LinkedFile>) -> Vec<LinkedFile> {
+    if !SERVER_CONFIG.bundle.is_enabled() {
+        return files;
+    }
+
+    let bundles = BUNDLES.load();
+    let is_bundled =
+        |file: &LinkedFile| file.is_local() && !file.has_options() && bundles.can_bundle(&file.url);
+    let mut bundled = Vec::new();
+
+    for chunk in files.chunk_by(|a, b| is_bundled(a) == is_bundled(b)) {
+        match chunk {
+            [first, _, ..] if is_bundled(first) => {
+                let urls = chunk
+                    .iter()
+                    .map(|file| file.url.clone())
+                    .collect::<Vec<_>>();
+
+                // Built now, so that it can be served and its integrity is known
+                match Arc::clone(&bundles).register(urls).await {
+                    Some(url) => bundled.push(LinkedFile::new(url)),
+                    None => bundled.extend_from_slice(chunk),
+                }
+            },
+            _ => bundled.extend_from_slice(chunk),
+        }
+    }
+
+    bundled
+}
+
+
+impl Bundle {
+    /// Concatenate the files, or `None` if one of them can't be read
+    fn new(url: &str, files: &[String]) -> Option<Self> {
+        let is_css = url.ends_with(".css");
+        let should_minify = matches!(*ENV_TYPE, EnvType::Prod | EnvType::Staging);
+
+        let mut contents = Vec::with_capacity(files.len());
+        for file in files {
+            let content = read(file)?;
+            let should_minify = should_minify && !file.contains(".min.");
+
+            contents.push(
+                if is_css {
+                    bundled_css(file, &content, should_minify)
+                        .map_err(|err| log::warn!("Couldn't bundle `{file}`: {err}"))
+                        .ok()?
+                } else if should_minify {
+                    minify_js(file, &content)
+                } else {
+                    content
+                },
+            );
+        }
+
+        // A JavaScript file that doesn't end with a semicolon could continue in the next one
+        let content = contents.join(if is_css { "\n" } else { "\n;\n" });
+        let hash = assets::hash(content.as_bytes(), ETAG_HASH_LEN);
+
+        Some(Self {
+            precompressed: Precompressed::new(url, content.as_bytes()),
+            integrity: integrity::integrity(content.as_bytes()),
+            content: Bytes::from(content),
+            content_type: if is_css {
+                "text/css; charset=utf-8"
+            } else {
+                "text/javascript; charset=utf-8"
+            },
+            validators: Validators {
+                etag: format!("\"{hash}\""),
+                last_modified: None,
+            },
+        })
+    }
+}
+
+/// The content of a file of `src/`, or of the public files for the packages
+fn read(url: &str) -> Option<String> {
+    let (css_files, js_files) = (CSS_FILES.load(), JS_FILES.load());
+    if let Some(content) = css_files.get(url).or_else(|| js_files.get(url)) {
+        return Some(content.clone());
+    }
+
+    match std::fs::read_to_string(format!("./public{url}")) {
+        Ok(content) => Some(content),
+        Err(err) => {
+            log::warn!("Couldn't bundle `{url}`, the files are linked one by one: {err}");
+            None
+        },
+    }
+}
+
+
+/// Whether a style sheet still works in a bundle: the `@import` and `@namespace` rules are
+/// ignored if they aren't at the start of the file
+pub fn can_bundle_css(url: &str, css: &str) -> bool {
+    match StyleSheet::parse(css, parser_options(url)) {
+        Ok(stylesheet) => {
+            let has_first_rules = stylesheet
+                .rules
+                .0
+                .iter()
+                .any(|rule| matches!(rule, CssRule::Import(_) | CssRule::Namespace(_)));
+            if has_first_rules {
+                log::info!("`{url}` isn't bundled, since it has `@import` or `@namespace` rules");
+            }
+            !has_first_rules
+        },
+        Err(err) => {
+            log::warn!("Couldn't parse `{url}`, it isn't bundled: {err}");
+            false
+        },
+    }
+}
+
+/// A style sheet as it is in a bundle: with the relative URLs of its `url()`s made absolute from
+/// `url`, since the bundles are in another directory, and minified if `minify`
+pub fn bundled_css(url: &str, css: &str, minify: bool) -> Result<String, String> {
+    let mut stylesheet =
+        StyleSheet::parse(css, parser_options(url)).map_err(|err| err.to_string())?;
+
+    let directory = Path::new(url.trim_start_matches('/'))
+        .parent()
+        .unwrap_or_else(|| Path::new(""));
+    let Ok(()) = stylesheet.visit(&mut AbsoluteUrls { directory });
+
+    if minify {
+        stylesheet
+            .minify(MinifyOptions::default())
+            .map_err(|err| err.to_string())?;
+    }
+
+    stylesheet
+        .to_css(PrinterOptions {
+            minify,
+            ..PrinterOptions::default()
+        })
+        .map(|result| result.code)
+        .map_err(|err| err.to_string())
+}
+
+fn parser_options<'i>(url: &str) -> ParserOptions<'i> {
+    ParserOptions {
+        filename: url.to_owned(),
+        ..ParserOptions::default()
+    }
+}
+
+/// Make the relative URLs of a style sheet in `directory` absolute
+struct AbsoluteUrls<'d> {
+    directory: &'d Path,
+}
+
+impl<'i> Visitor<'i> for AbsoluteUrls<'_> {
+    type Error = Infallible;
+
+    fn visit_types(&self) -> VisitTypes {
+        visit_types!(URLS)
+    }
+
+    fn visit_url(&mut self, url: &mut Url<'i>) -> Result<(), Self::Error> {
+        // Such as `data:...`, `https://...`, `/image.png` or `#filter`
+        let is_absolute = url.url.starts_with(['/', '#']) ||
+            url.url
+                .split(['/', '?', '#'])
+                .next()
+                .is_some_and(|scheme| scheme.contains(':'));
+
+        if !is_absolute {
+            url.url = format!(
+                "/{}",
+                normalize_relative_path(self.directory.join(&*url.url)).display()
+            )
+            .into();
+        }
+
+        Ok(())
+    }
+}
+
+/// Minify JavaScript, or keep it as is if it can't be parsed
+fn minify_js(url: &str, js: &str) -> String {
+    let session = Session::new();
+    let mut minified = Vec::new();
+
+    match minify_js::minify(&session, TopLevelMode::Global, js.as_bytes(), &mut minified) {
+        Ok(()) => String::from_utf8(minified).unwrap_or_else(|_| js.to_owned()),
+        Err(err) => {
+            log::warn!("Couldn't minify `{url}`, it's bundled as it is: {err:?}");
+            js.to_owned()
+        },
+    }
+}
@@ -26,6 +26,8 @@ pub enum CacheCategory {
     Css,
     Javascript,
     Public,
+    /// The CSS and JavaScript files served at their fingerprinted URL, that never change
+    Fingerprinted,
     Function,
 }
 
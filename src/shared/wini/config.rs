@@ -1,8 +1,17 @@
 use {
     super::{ENV_TYPE, cache::CacheCategory, dependencies::normalize_relative_path, env::EnvType},
     crate::concat_paths,
+    hyper::header::{
+        CONTENT_SECURITY_POLICY,
+        HeaderName,
+        HeaderValue,
+        REFERRER_POLICY,
+        STRICT_TRANSPORT_SECURITY,
+        X_CONTENT_TYPE_OPTIONS,
+    },
     serde::{Deserialize, Deserializer},
     std::{
+        borrow::Cow,
         collections::HashMap,
         fmt::Display,
         io,
@@ -27,6 +36,16 @@ pub static SERVER_CONFIG: LazyLock<Arc<Config>> = LazyLock::new(|| {
 pub struct Config {
     pub path: ConfigPath,
     pub cache: Caches,
+    #[serde(default)]
+    pub security: Security,
+    #[serde(default)]
+    pub session: SessionConfig,
+    #[serde(default)]
+    pub errors: ErrorPages,
+    #[serde(default)]
+    pub bundle: Bundling,
+    #[serde(default)]
+    pub scripts: ScriptsConfig,
 }
 
 impl Config {
@@ -160,6 +179,186 @@ impl Caches {
 }
 
 
+/// The security headers sent with every response. A header that isn't specified isn't sent.
+///
+/// In `content_security_policy`, `{nonce}` is replaced by the nonce of the request, that is also
+/// put on the `<script>` and `<link>` tags of the template.
+///
+/// `strict_transport_security` isn't sent in the `Local` environment.
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct Security {
+    pub content_security_policy: Option<String>,
+    pub strict_transport_security: Option<String>,
+    pub x_content_type_options: Option<String>,
+    pub referrer_policy: Option<String>,
+    pub permissions_policy: Option<String>,
+}
+
+impl Security {
+    /// The headers to send, with their value for this nonce
+    pub fn headers(&self, nonce: &str) -> [(HeaderName, Option<Cow<'_, str>>); 5] {
+        [
+            (
+                CONTENT_SECURITY_POLICY,
+                self.content_security_policy
+                    .as_deref()
+                    .map(|csp| Cow::Owned(csp.replace("{nonce}", nonce))),
+            ),
+            // The `Local` environment is served over HTTP, and the browsers would keep using HTTPS
+            // for `localhost` afterwards
+            (
+                STRICT_TRANSPORT_SECURITY,
+                self.strict_transport_security
+                    .as_deref()
+                    .filter(|_| *ENV_TYPE != EnvType::Local)
+                    .map(Cow::Borrowed),
+            ),
+            (
+                X_CONTENT_TYPE_OPTIONS,
+                self.x_content_type_options.as_deref().map(Cow::Borrowed),
+            ),
+            (
+                REFERRER_POLICY,
+                self.referrer_policy.as_deref().map(Cow::Borrowed),
+            ),
+            (
+                HeaderName::from_static("permissions-policy"),
+                self.permissions_policy.as_deref().map(Cow::Borrowed),
+            ),
+        ]
+    }
+
+    /// Whether the policy allows the tags with the nonce of the request. The pages that carry it
+    /// can't be revalidated, since the policy of a new response wouldn't allow their nonce.
+    pub fn uses_nonce(&self) -> bool {
+        self.content_security_policy
+            .as_deref()
+            .is_some_and(|csp| csp.contains("{nonce}"))
+    }
+
+    /// Verify that all the security headers are valid header values
+    pub fn verify_all_attributes(&self) {
+        for (name, value) in self.headers("nonce") {
+            if let Some(value) = value &&
+                HeaderValue::from_str(&value).is_err()
+            {
+                log::error!(
+                    "The value of the header `{name}` isn't a valid header value.\n\
+                    Look at your security definitions in `./wini.toml`"
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+
+/// How the errors are shown to the users
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorPageKind {
+    /// The error, with the functions it went through and their source code
+    Detailed,
+    /// A generic page, with only an ID to find the error in the logs
+    Generic,
+}
+
+/// The kind of error page for different kind of environments
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct ErrorPages {
+    default: Option<ErrorPageKind>,
+    #[serde(flatten)]
+    environments: HashMap<EnvType, ErrorPageKind>,
+}
+
+impl ErrorPages {
+    /// The kind of error page of the current environment. Without configuration, the errors are
+    /// detailed only in `Local` and `Dev`.
+    pub fn current(&self) -> ErrorPageKind {
+        self.environments
+            .get(&ENV_TYPE)
+            .copied()
+            .or(self.default)
+            .unwrap_or(match *ENV_TYPE {
+                EnvType::Local | EnvType::Dev => ErrorPageKind::Detailed,
+                EnvType::Staging | EnvType::Prod => ErrorPageKind::Generic,
+            })
+    }
+}
+
+
+/// Whether the CSS and JavaScript files of each page are bundled, for different kind of
+/// environments
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct Bundling {
+    default: Option<bool>,
+    #[serde(flatten)]
+    environments: HashMap<EnvType, bool>,
+}
+
+impl Bundling {
+    /// Whether the files are bundled in the current environment. Without configuration, they
+    /// aren't.
+    pub fn is_enabled(&self) -> bool {
+        self.environments
+            .get(&ENV_TYPE)
+            .copied()
+            .or(self.default)
+            .unwrap_or(false)
+    }
+}
+
+
+/// How the scripts of the pages are linked
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct ScriptsConfig {
+    /// Link them as ES modules, that import each other with an import map, instead of classic
+    /// scripts linked after their dependencies
+    pub modules: bool,
+}
+
+
+/// Where the data of the sessions is stored
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionStoreKind {
+    /// In the cookie itself. Limited to 4KB.
+    Cookie,
+    /// In the memory of the server. Lost on restart.
+    #[default]
+    Memory,
+    /// In a directory, one file per session
+    File,
+}
+
+/// The configuration of the sessions
+#[derive(Debug, serde::Deserialize)]
+#[serde(default)]
+pub struct SessionConfig {
+    pub cookie_name: String,
+    pub store: SessionStoreKind,
+    /// The directory of the `file` store
+    pub path: String,
+    /// Encrypt the content of the cookie, in addition to signing it
+    pub encrypt: bool,
+    /// In seconds
+    pub max_age: u64,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            cookie_name: "wini_session".to_owned(),
+            store: SessionStoreKind::default(),
+            path: "./sessions".to_owned(),
+            encrypt: false,
+            max_age: 60 * 60 * 24 * 7,
+        }
+    }
+}
+
+
 #[derive(Debug)]
 pub enum TomlLoadingError {
     ConfigFileDoesntExists(String),
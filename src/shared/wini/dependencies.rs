@@ -1,35 +1,28 @@
+//! The dependencies of the scripts: the files and packages they import, so that they are linked
+//! after them in the pages.
+//!
+//! The graph of the imports is built when the files are loaded. The circular imports are reported
+//! then, with the scripts they go through.
+
 use {
     super::{
+        CSS_FILES,
         JS_FILES,
-        err::ExitWithMessageIfErr,
-        tsconfig::{TSCONFIG_PATHS, TsConfigPathsPrefix},
+        config::SERVER_CONFIG,
+        imports::imports,
+        reloadable::Reloadable,
+        tsconfig::TSCONFIG_PATHS,
     },
     crate::concat_paths,
-    regex::Regex,
     std::{
-        collections::HashMap,
+        collections::{BTreeSet, HashMap, HashSet},
         path::{Component, Path, PathBuf},
-        sync::LazyLock,
     },
 };
 
-pub static REGEX_DEPENDENCY: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(r#"(import|from)\s*["']([^'"]+)["'](;|\n)"#)
-        .expect("This should always be a valid regex.")
-});
-
-pub static REGEX_IS_PACKAGE: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"^[A-Za-z_0-9]").expect("This should always be a valid regex."));
-
-pub static SCRIPTS_DEPENDENCIES: LazyLock<HashMap<String, Option<Vec<String>>>> =
-    LazyLock::new(|| {
-        LazyLock::force(&REGEX_IS_PACKAGE);
 
-        JS_FILES
-            .keys()
-            .map(|script| (script.to_owned(), script_dependencies(script)))
-            .collect()
-    });
+pub static SCRIPTS_DEPENDENCIES: Reloadable<DependencyGraph> =
+    Reloadable::new(DependencyGraph::build);
 
 
 /// Normalizes a relative file path by resolving `.` (current directory) and `..` (parent directory) components.
@@ -79,142 +72,282 @@ pub fn normalize_relative_path<P: AsRef<Path>>(path: P) -> PathBuf {
 }
 
 
-/// Get the dependencies of a JavaScript file.
-///
-/// This only gets the files or packages that a file needs.
-/// If a package depend on other packages, they will not be included.
-/// This is used to easily import <script/>s in head
-///
-/// # Example:
-/// `file1.js` // require("./file2")
-/// `file2.js` // require("debug")
-///
-/// Will produce the following slice:
-/// `["file2.js", "debug"]`.
-///
-/// This should be converted to
-///
-/// ```html
-/// <head>
-///     ...
-///     <script src="path/to/debug.min.js"></script>
-///     <script src="file2.js"></script>
-///     ...
-/// </head>
-///
-/// # Panic
-///
-/// If there is an error finding a dependency
-fn script_dependencies(path: &str) -> Option<Vec<String>> {
-    let mut path_str = path.strip_prefix("/").unwrap_or(path).replace(".js", ".ts");
-    let mut path = std::path::Path::new(&path_str);
 
-    if !path.exists() {
-        path_str = path_str.replace(".ts", ".js");
-        path = std::path::Path::new(&path_str);
-    }
+/// What a script imports
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Dependency {
+    /// A script of `src/` or of the public files, by URL, such as `/src/utils/date.js`
+    Script(String),
+    /// A package of `packages-files.toml`, by name
+    Package(String),
+}
 
-    let contents = std::fs::read_to_string(path).exit_with_msg_if_err("IO Error");
+/// The imports of the scripts
+#[derive(Debug, Default)]
+pub struct DependencyGraph {
+    /// The dependencies of each script, in the order of its imports
+    dependencies: HashMap<String, Vec<Dependency>>,
+    /// The circular imports, each one with the scripts it goes through, from and to the same one
+    cycles: Vec<Vec<String>>,
+}
 
-    let caps = REGEX_DEPENDENCY.captures_iter(&contents);
+impl DependencyGraph {
+    fn build() -> Self {
+        let graph = Self::new(JS_FILES.load().keys().cloned(), read_script);
 
-    let dependencies = caps
-        .into_iter()
-        .map(|m| m.extract::<3>())
-        .map(|ex| ex.1[1].to_string())
-        .collect::<Vec<String>>();
-
-    if dependencies.is_empty() {
-        None
-    } else {
-        let mut relatives_dependencies = vec![];
-
-        // Ok to clone since this function will only be called on initialization of LazyLock
-        for dep in dependencies {
-            let is_dep_package = REGEX_IS_PACKAGE.is_match(&dep);
-
-            // We need to convert the dependency to it's correct path
-
-            // If an import starts with a ".", it's a path to a file. In this case, we want to
-            // have it's path relative to the file it's referenced from.
-            let dep_relative_path = if dep.starts_with('.') {
-                let dep = concat_paths!(
-                    path.parent().expect("Path should have a parent."),
-                    if dep.ends_with(".js") {
-                        dep
-                    } else {
-                        dep + ".ts"
-                    }
-                )
-                .to_str()
-                .expect("Not empty.")
-                .to_string();
+        for cycle in &graph.cycles {
+            log::error!(
+                "Circular import between scripts: {}. They can't all be linked after their \
+                 dependencies.",
+                cycle.join(" -> ")
+            );
+        }
 
-                normalize_relative_path(dep).display().to_string()
+        graph
+    }
+
+    /// The graph of the imports of `scripts`, and of the scripts they import. `read` gives the
+    /// source of a script from its URL, or `None` if it doesn't exist.
+    pub fn new<S, F>(scripts: S, read: F) -> Self
+    where
+        S: IntoIterator<Item = String>,
+        F: Fn(&str) -> Option<String>,
+    {
+        let mut graph = Self::default();
+        // Sorted, so that the graph, and the order of the cycles, doesn't depend on the order of
+        // `scripts`
+        let mut to_visit = scripts.into_iter().collect::<BTreeSet<_>>();
+
+        while let Some(script) = to_visit.pop_first() {
+            if graph.dependencies.contains_key(&script) {
+                continue;
             }
-            // Resolve tsconfig paths. <=> If it's a file that needs to be resolved with
-            // `tsconfig.compilerOptions.paths`.
-            else if let Some(prefix_path) = TSCONFIG_PATHS
-                .prefixes()
-                .iter()
-                .find(|prefix| dep.starts_with(*prefix))
-            {
-                let vec = TSCONFIG_PATHS
-                    .get(*prefix_path)
-                    .expect("Already matched the key");
-
-                // If there is only one path to resolve, we know which one it is! (the first)
-                if let Some(first) = vec.first() {
-                    concat_paths!(first, &dep[prefix_path.len()..])
-                        .display()
-                        .to_string()
-                } else {
-                    let mut resolved_path = None;
-
-                    for path in vec {
-                        let relative_path =
-                            concat_paths!(path, format!(".{}.js", &dep[prefix_path.len()..]))
-                                .display()
-                                .to_string();
-
-                        // When there is a first match, we break
-                        if Path::new(&relative_path).is_file() {
-                            resolved_path = Some(relative_path);
-                            break;
-                        }
-                    }
 
-                    if let Some(path) = resolved_path {
-                        path
-                    } else {
-                        log::warn!("Couldn't find a file corresponding to {dep:#?}");
+            let Some(source) = read(&script) else {
+                log::warn!("Couldn't read the script `{script}` to find its imports");
+                graph.dependencies.insert(script, Vec::new());
+                continue;
+            };
+
+            let mut dependencies = Vec::new();
+            for import in imports(&source) {
+                let Some(dependency) = resolve(&script, &import) else {
+                    continue;
+                };
+
+                if let Dependency::Script(imported) = &dependency {
+                    if read(imported).is_none() {
+                        log::warn!("Couldn't find `{import}`, imported by `{script}`");
                         continue;
                     }
+                    to_visit.insert(imported.clone());
+                }
+
+                if !dependencies.contains(&dependency) {
+                    dependencies.push(dependency);
                 }
             }
-            // Else it's just a package
-            else {
-                dep
-            };
 
+            graph.dependencies.insert(script, dependencies);
+        }
 
-            relatives_dependencies.push(dep_relative_path.clone());
+        graph.cycles = graph.find_cycles();
+        graph
+    }
 
-            // If it's not a package, we need to look at the dependencies of this file
-            if !is_dep_package && let Some(sub_deps) = script_dependencies(&dep_relative_path) {
-                for sub_dep in sub_deps {
-                    if relatives_dependencies.contains(&sub_dep) {
-                        let maybe_index = relatives_dependencies.iter().position(|d| *d == sub_dep);
-                        if let Some(index) = maybe_index {
-                            relatives_dependencies.remove(index);
-                        }
-                    }
+    /// The circular imports, each one with the scripts it goes through, from and to the same one
+    pub fn cycles(&self) -> &[Vec<String>] {
+        &self.cycles
+    }
 
-                    relatives_dependencies.push(sub_dep);
-                }
+    /// The dependencies of a script, in the order of its imports
+    pub fn get(&self, script: &str) -> Option<&[Dependency]> {
+        self.dependencies.get(script).map(Vec::as_slice)
+    }
+
+    /// `scripts`, and everything they import, each one after its dependencies. The order only
+    /// depends on the order of `scripts` and of the imports.
+    pub fn order<'s, S>(&self, scripts: S) -> Vec<Dependency>
+    where
+        S: IntoIterator<Item = &'s str>,
+    {
+        let mut visited = HashSet::new();
+        let mut ordered = Vec::new();
+
+        for script in scripts {
+            self.visit(
+                &Dependency::Script(script.to_owned()),
+                &mut visited,
+                &mut ordered,
+            );
+        }
+
+        ordered
+    }
+
+    /// Add `dependency` to `ordered` after its dependencies. A script is marked as visited before
+    /// its dependencies, so that the circular imports stop.
+    fn visit(
+        &self,
+        dependency: &Dependency,
+        visited: &mut HashSet<Dependency>,
+        ordered: &mut Vec<Dependency>,
+    ) {
+        if !visited.insert(dependency.clone()) {
+            return;
+        }
+
+        if let Dependency::Script(script) = dependency {
+            for dependency in self.dependencies.get(script).into_iter().flatten() {
+                self.visit(dependency, visited, ordered);
             }
         }
 
-        Some(relatives_dependencies)
+        ordered.push(dependency.clone());
+    }
+
+    fn find_cycles(&self) -> Vec<Vec<String>> {
+        let mut cycles = Vec::new();
+        let mut done = HashSet::new();
+        let mut scripts = self.dependencies.keys().collect::<Vec<_>>();
+        scripts.sort();
+
+        for script in scripts {
+            self.find_cycles_from(script, &mut Vec::new(), &mut done, &mut cycles);
+        }
+
+        cycles
+    }
+
+    /// Depth-first search of the cycles, with `path` the scripts from the start to `script`
+    fn find_cycles_from<'g>(
+        &'g self,
+        script: &'g str,
+        path: &mut Vec<&'g str>,
+        done: &mut HashSet<&'g str>,
+        cycles: &mut Vec<Vec<String>>,
+    ) {
+        if done.contains(script) {
+            return;
+        }
+
+        if let Some(start) = path.iter().position(|visited| *visited == script) {
+            let mut cycle = path
+                .get(start..)
+                .unwrap_or_default()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            cycle.push(script.to_owned());
+            cycles.push(cycle);
+            return;
+        }
+
+        path.push(script);
+        for dependency in self.dependencies.get(script).into_iter().flatten() {
+            if let Dependency::Script(imported) = dependency {
+                self.find_cycles_from(imported, path, done, cycles);
+            }
+        }
+        path.pop();
+
+        done.insert(script);
+    }
+}
+
+
+/// The source of a script from its URL: its TypeScript file if there is one, or else the
+/// JavaScript one
+fn read_script(url: &str) -> Option<String> {
+    let public_url = url
+        .strip_prefix('/')
+        .filter(|_| !JS_FILES.load().contains_key(url))
+        .map(|_| format!("./public{url}"));
+    let path = public_url.unwrap_or_else(|| url.trim_start_matches('/').to_owned());
+
+    [path.replace(".js", ".ts"), path]
+        .into_iter()
+        .find_map(|path| std::fs::read_to_string(path).ok())
+}
+
+/// What `import` means in `script`
+fn resolve(script: &str, import: &str) -> Option<Dependency> {
+    // The scripts of other websites are loaded by the browser
+    if import.contains("://") {
+        return None;
+    }
+
+    if import.starts_with('/') {
+        return Some(Dependency::Script(with_js_extension(import)));
+    }
+
+    // A path relative to the script
+    if import.starts_with('.') {
+        let directory = Path::new(script.trim_start_matches('/'))
+            .parent()
+            .unwrap_or_else(|| Path::new(""));
+        return Some(Dependency::Script(with_js_extension(&url_of_path(
+            &concat_paths!(directory, import),
+        ))));
+    }
+
+    // A path of `tsconfig.compilerOptions.paths`, with the first target in which it exists, or
+    // else from its `baseUrl`, like `tsc`
+    let mapped = TSCONFIG_PATHS.mapped(import);
+    let candidates = mapped
+        .iter()
+        .flatten()
+        .cloned()
+        .chain(TSCONFIG_PATHS.from_base_url(import))
+        .flat_map(|path| {
+            let url = url_of_path(&path);
+            [with_js_extension(&url), format!("{url}/index.js")]
+        });
+    for url in candidates {
+        if read_script(&url).is_some() {
+            return Some(Dependency::Script(url));
+        }
+    }
+
+    if let Some(paths) = mapped {
+        return match paths.first() {
+            Some(path) => {
+                log::warn!("Couldn't find `{import}`, imported by `{script}`");
+                Some(Dependency::Script(with_js_extension(&url_of_path(path))))
+            },
+            None => {
+                log::warn!("Couldn't resolve `{import}`, imported by `{script}`");
+                None
+            },
+        };
+    }
+
+    // Styles imported by a script are linked by the page
+    if import.ends_with(".css") && CSS_FILES.load().contains_key(import) {
+        return None;
+    }
+
+    Some(Dependency::Package(import.to_owned()))
+}
+
+/// The URL of a file or a directory from its path relative to the root of the project. The files
+/// of the public directory are served at the root.
+pub fn url_of_path(path: &Path) -> String {
+    let path = normalize_relative_path(path);
+    let public = normalize_relative_path(concat_paths!("src", &SERVER_CONFIG.path.public));
+
+    match path.strip_prefix(&public) {
+        Ok(public_path) => format!("/{}", public_path.display()),
+        Err(_) => format!("/{}", path.display()),
+    }
+}
+
+/// The URL of the compiled JavaScript of an import, that can be written without its extension,
+/// or with the one of TypeScript
+fn with_js_extension(url: &str) -> String {
+    match Path::new(url).extension().and_then(|ext| ext.to_str()) {
+        Some("js" | "mjs") => url.to_owned(),
+        Some("ts") => format!("{}.js", url.trim_end_matches(".ts")),
+        _ => format!("{url}.js"),
     }
 }
@@ -1,4 +1,5 @@
 use {
+    super::error_page,
     axum::response::{IntoResponse, Response},
     hyper::{
         header::{InvalidHeaderValue, ToStrError},
@@ -17,6 +18,12 @@ pub struct ServerError {
 }
 
 impl ServerError {
+    /// Render the error where the content that failed should have been, for the errors that happen
+    /// once the response is sent, such as in deferred components
+    pub fn render_inline(&self) -> Markup {
+        error_page::render_inline(&self.kind, self.trace.as_deref().unwrap_or_default())
+    }
+
     pub fn add_trace(&mut self, trace: Trace) {
         match &mut self.trace {
             Some(curr_trace) => {
@@ -38,6 +45,8 @@ pub enum ServerErrorKind {
     DebugedError(String),
     PublicRessourceNotFound(String),
     ToStrError(ToStrError),
+    /// A panic caught while handling a request, with its message and location
+    Panic(String),
 }
 
 impl From<ServerErrorKind> for ServerError {
@@ -77,33 +86,43 @@ impl_from_error!(InvalidHeaderValue, ServerErrorKind::InvalidHeader);
 impl_from_error!(ToStrError, ServerErrorKind::ToStrError);
 
 
-impl IntoResponse for &ServerErrorKind {
-    fn into_response(self) -> Response {
-        eprintln!("{self:#?}");
-        let err_msg = match self {
-            ServerErrorKind::InvalidHeader(err) => {
-                format!("Unexpected header value: {err}")
-            },
-            ServerErrorKind::DebugedError(err) => {
-                format!("Unexpected error: {err}")
-            },
+impl std::fmt::Display for ServerErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServerErrorKind::InvalidHeader(err) => write!(f, "Unexpected header value: {err}"),
+            ServerErrorKind::DebugedError(err) => write!(f, "Unexpected error: {err}"),
             ServerErrorKind::Infallible(err) => {
-                format!("This error should not be possible: {err:#?}")
-            },
-            ServerErrorKind::ToStrError(err) => {
-                format!("Invalid str: {err}")
+                write!(f, "This error should not be possible: {err:#?}")
             },
+            ServerErrorKind::ToStrError(err) => write!(f, "Invalid str: {err}"),
             ServerErrorKind::Utf8Error(err) => {
-                format!("Error decoding buffer to UTF-8: {err:#?}")
+                write!(f, "Error decoding buffer to UTF-8: {err:#?}")
             },
             ServerErrorKind::PublicRessourceNotFound(path) => {
-                return (StatusCode::NOT_FOUND, format!("Couldn't find file: {path}"))
-                    .into_response();
+                write!(f, "Couldn't find file: {path}")
+            },
+            ServerErrorKind::Status(status_code) => write!(f, "{status_code}"),
+            ServerErrorKind::Panic(message) => write!(f, "Panic: {message}"),
+        }
+    }
+}
+
+impl ServerErrorKind {
+    /// The response of this error, that went through the functions of `trace`
+    fn response_with_trace(&self, trace: &[Trace]) -> Response {
+        match self {
+            ServerErrorKind::PublicRessourceNotFound(_) => {
+                (StatusCode::NOT_FOUND, self.to_string()).into_response()
             },
-            ServerErrorKind::Status(status_code) => return status_code.into_response(),
-        };
+            ServerErrorKind::Status(status_code) => status_code.into_response(),
+            _ => error_page::render(self, trace),
+        }
+    }
+}
 
-        (StatusCode::INTERNAL_SERVER_ERROR, err_msg).into_response()
+impl IntoResponse for &ServerErrorKind {
+    fn into_response(self) -> Response {
+        self.response_with_trace(&[])
     }
 }
 
@@ -115,13 +134,14 @@ impl IntoResponse for ServerErrorKind {
 
 impl IntoResponse for &ServerError {
     fn into_response(self) -> Response {
-        self.kind.as_ref().into_response()
+        self.kind
+            .response_with_trace(self.trace.as_deref().unwrap_or_default())
     }
 }
 
 impl IntoResponse for ServerError {
     fn into_response(self) -> Response {
-        self.kind.as_ref().into_response()
+        (&self).into_response()
     }
 }
 
@@ -191,6 +211,19 @@ pub struct Trace {
     pub function_name: &'static str,
 }
 
+/// The error page of a `#[page]`, that goes through its layouts: they can use the
+/// `Option<Backtrace>` of the response
+impl IntoResponse for &Backtrace {
+    fn into_response(self) -> Response {
+        match self.err.as_ref() {
+            err @ (ServerErrorKind::PublicRessourceNotFound(_) | ServerErrorKind::Status(_)) => {
+                err.into_response()
+            },
+            err => error_page::render_in_page(err, &self.trace),
+        }
+    }
+}
+
 impl From<ServerError> for Backtrace {
     fn from(value: ServerError) -> Self {
         Self {
@@ -0,0 +1,218 @@
+//! The pages sent when a request fails, detailed or generic depending on the environment.
+//! See the `[errors]` section of `wini.toml`.
+
+use {
+    super::{
+        config::{ErrorPageKind, SERVER_CONFIG},
+        err::{ServerErrorKind, Trace},
+        layer::Fragment,
+    },
+    axum::response::{Html, IntoResponse, Response},
+    hyper::{
+        StatusCode,
+        header::{CACHE_CONTROL, HeaderName},
+    },
+    maud::{DOCTYPE, Markup, html},
+    std::path::Path,
+};
+
+
+/// The header in which the ID of the error is sent, to find it in the logs
+pub const ERROR_ID_HEADER: HeaderName = HeaderName::from_static("x-error-id");
+
+/// The number of lines shown before the definition of a function
+const LINES_BEFORE: usize = 2;
+
+/// The number of lines shown from the definition of a function
+const LINES_AFTER: usize = 12;
+
+/// Scoped to `.wini-error`, since the page can be rendered inside of the layouts
+const STYLE: &str = "
+    .wini-error { font-family: system-ui, sans-serif; margin: 0 auto; max-width: 60rem; padding: 2rem; color: #222; }
+    .wini-error h1 { color: #b3261e; }
+    .wini-error pre { background: #f5f5f5; padding: 1rem; overflow-x: auto; border-radius: 0.5rem; }
+    .wini-error .frame { margin-bottom: 2rem; }
+    .wini-error .file { color: #666; }
+    .wini-error .line-number { color: #999; user-select: none; }
+    .wini-error .definition { background: #fde8e6; }
+    .wini-error .id { font-family: monospace; }
+";
+
+
+/// The page of an error that went through the functions of `trace`, the first one being where it
+/// happened.
+///
+/// The error is logged with an ID, that is shown on the page.
+pub fn render(err: &ServerErrorKind, trace: &[Trace]) -> Response {
+    respond(err, trace, true)
+}
+
+/// Like [`render`], but only the content of the page is rendered, so that it goes through the
+/// layouts and the template
+pub fn render_in_page(err: &ServerErrorKind, trace: &[Trace]) -> Response {
+    respond(err, trace, false)
+}
+
+/// Only the content of the page of an error, for the errors that happen once the page is sent,
+/// such as in deferred components
+pub fn render_inline(err: &ServerErrorKind, trace: &[Trace]) -> Markup {
+    content(err, trace, &log_error(err, trace))
+}
+
+/// Log an error with a new ID, and return the ID
+fn log_error(err: &ServerErrorKind, trace: &[Trace]) -> String {
+    let id = format!("{:016x}", rand::random::<u64>());
+
+    log::error!(
+        "Error {id}: {err}\n{err:#?}{}",
+        trace
+            .iter()
+            .map(|frame| format!("\n    at {} ({})", frame.function_name, frame.file_path))
+            .collect::<String>()
+    );
+
+    id
+}
+
+fn content(err: &ServerErrorKind, trace: &[Trace], id: &str) -> Markup {
+    let content = match SERVER_CONFIG.errors.current() {
+        ErrorPageKind::Detailed => detailed(err, trace, StatusCode::INTERNAL_SERVER_ERROR, id),
+        ErrorPageKind::Generic => generic(id),
+    };
+
+    html! {
+        style { (STYLE) }
+        div.wini-error { (content) }
+    }
+}
+
+fn respond(err: &ServerErrorKind, trace: &[Trace], is_full_document: bool) -> Response {
+    let id = log_error(err, trace);
+    let status = StatusCode::INTERNAL_SERVER_ERROR;
+    let content = content(err, trace, &id);
+    let page = if is_full_document {
+        document(status, &content)
+    } else {
+        content
+    };
+
+    let mut resp = (
+        status,
+        [
+            (ERROR_ID_HEADER, id),
+            (CACHE_CONTROL, "no-store".to_owned()),
+        ],
+        Html(page.into_string()),
+    )
+        .into_response();
+    if is_full_document {
+        // It doesn't go through the layouts and the template
+        resp.extensions_mut().insert(Fragment);
+    }
+    resp
+}
+
+
+/// A page without any information about the error, apart from its ID
+fn generic(id: &str) -> Markup {
+    html! {
+        h1 { "Something went wrong" }
+        p { "An unexpected error happened. Please try again later." }
+        p { "If the problem persists, contact us with this error ID: " span.id { (id) } }
+    }
+}
+
+/// A page with the error, and the functions it went through with their source code
+fn detailed(err: &ServerErrorKind, trace: &[Trace], status: StatusCode, id: &str) -> Markup {
+    html! {
+        h1 { (status) }
+        p { strong { (err) } }
+        pre { (format!("{err:#?}")) }
+
+        h2 { "Backtrace" }
+        @if trace.is_empty() {
+            p { "The error didn't go through any page or component." }
+        }
+        @for frame in trace {
+            div.frame {
+                h3 { code { (frame.function_name) } }
+                p.file { (relative_path(frame.file_path)) }
+                @if let Some(source) = source_context(frame) {
+                    pre {
+                        @for (idx, line) in source.lines.iter().enumerate() {
+                            @let line_number = source.first_line + idx;
+                            span class=[(line_number == source.definition_line).then_some("definition")] {
+                                span.line-number { (format!("{line_number:>5} ")) }
+                                (line)
+                            }
+                            "\n"
+                        }
+                    }
+                }
+            }
+        }
+
+        p { "Error ID: " span.id { (id) } }
+    }
+}
+
+fn document(status: StatusCode, content: &Markup) -> Markup {
+    html! {
+        (DOCTYPE)
+        html lang="en" {
+            head {
+                meta charset="UTF-8";
+                meta name="viewport" content="width=device-width, initial-scale=1.0";
+                title { (status) }
+            }
+            body { (content) }
+        }
+    }
+}
+
+
+/// The path of a file, relative to the root of the project if it's inside of it
+fn relative_path(file_path: &str) -> String {
+    std::env::current_dir()
+        .ok()
+        .and_then(|current_dir| {
+            Path::new(file_path)
+                .strip_prefix(current_dir)
+                .ok()
+                .map(|path| path.display().to_string())
+        })
+        .unwrap_or_else(|| file_path.to_owned())
+}
+
+/// Lines of a source file. The numbers of the lines start at 1.
+struct SourceContext {
+    first_line: usize,
+    /// The line where the function is defined
+    definition_line: usize,
+    lines: Vec<String>,
+}
+
+/// The lines around the definition of the function of a frame
+fn source_context(frame: &Trace) -> Option<SourceContext> {
+    let source = std::fs::read_to_string(frame.file_path).ok()?;
+    let lines = source.lines().collect::<Vec<_>>();
+
+    let definition = format!("fn {}", frame.function_name);
+    let definition_idx = lines.iter().position(|line| {
+        line.split_once(&definition)
+            .is_some_and(|(_, after)| after.starts_with(['(', '<']))
+    })?;
+
+    let first_idx = definition_idx.saturating_sub(LINES_BEFORE);
+
+    Some(SourceContext {
+        first_line: first_idx + 1,
+        definition_line: definition_idx + 1,
+        lines: lines
+            .iter()
+            .skip(first_idx)
+            .take(definition_idx - first_idx + LINES_AFTER)
+            .map(|line| (*line).to_owned())
+            .collect(),
+    })
+}
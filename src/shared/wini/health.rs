@@ -0,0 +1,18 @@
+//! Liveness and readiness of the server
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static IS_READY: AtomicBool = AtomicBool::new(false);
+
+/// Mark the server as ready to receive traffic.
+///
+/// This should be called once all the data of the server has been initialized: the `LazyLock`s
+/// forced in `main` and the `#[init_cache]` functions warmed up.
+pub fn mark_as_ready() {
+    IS_READY.store(true, Ordering::Release);
+}
+
+/// Is the server ready to receive traffic ?
+pub fn is_ready() -> bool {
+    IS_READY.load(Ordering::Acquire)
+}
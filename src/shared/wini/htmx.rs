@@ -0,0 +1,225 @@
+//! Typed access to the headers of htmx: the ones sent with its requests, and the ones of the
+//! responses that control what it does.
+//!
+//! See <https://htmx.org/reference/#headers>.
+
+use {
+    super::err::ServerError,
+    axum::{
+        extract::FromRequestParts,
+        http::request::Parts,
+        response::{IntoResponse, IntoResponseParts, Response, ResponseParts},
+    },
+    hyper::{
+        HeaderMap,
+        Uri,
+        header::{HeaderName, HeaderValue},
+    },
+    maud::{Markup, Render, html},
+    std::{convert::Infallible, fmt},
+};
+
+
+pub const HX_REQUEST: HeaderName = HeaderName::from_static("hx-request");
+pub const HX_TARGET: HeaderName = HeaderName::from_static("hx-target");
+pub const HX_TRIGGER: HeaderName = HeaderName::from_static("hx-trigger");
+pub const HX_CURRENT_URL: HeaderName = HeaderName::from_static("hx-current-url");
+pub const HX_HISTORY_RESTORE_REQUEST: HeaderName =
+    HeaderName::from_static("hx-history-restore-request");
+pub const HX_REDIRECT: HeaderName = HeaderName::from_static("hx-redirect");
+pub const HX_PUSH_URL: HeaderName = HeaderName::from_static("hx-push-url");
+pub const HX_RESWAP: HeaderName = HeaderName::from_static("hx-reswap");
+
+
+/// The headers sent by htmx with a request. All of them are empty if the request isn't made by
+/// htmx.
+///
+/// # Example
+///
+/// ```ignore
+/// #[page]
+/// pub async fn render(hx: HxRequest) -> Markup {
+///     html! {
+///         @if hx.is_htmx {
+///             p { "Loaded by htmx" }
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct HxRequest {
+    /// Whether the request is made by htmx (`HX-Request`)
+    pub is_htmx: bool,
+    /// The `id` of the element in which the response will be swapped, if it has one (`HX-Target`)
+    pub target: Option<String>,
+    /// The `id` of the element that triggered the request, if it has one (`HX-Trigger`)
+    pub trigger: Option<String>,
+    /// The URL of the browser when the request was made (`HX-Current-URL`)
+    pub current_url: Option<Uri>,
+    /// Whether the request restores the history of the browser, and therefore wants the full page
+    /// (`HX-History-Restore-Request`)
+    pub is_history_restore: bool,
+}
+
+impl HxRequest {
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let header = |name: &HeaderName| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(ToOwned::to_owned)
+        };
+
+        Self {
+            is_htmx: headers.get(HX_REQUEST).is_some_and(|value| value == "true"),
+            target: header(&HX_TARGET),
+            trigger: header(&HX_TRIGGER),
+            current_url: header(&HX_CURRENT_URL).and_then(|url| url.parse().ok()),
+            is_history_restore: headers
+                .get(HX_HISTORY_RESTORE_REQUEST)
+                .is_some_and(|value| value == "true"),
+        }
+    }
+
+    /// Whether htmx only wants the content of the page, to swap it into the target. See
+    /// [`crate::shared::wini::layer::is_fragment_request`].
+    pub fn is_fragment(&self) -> bool {
+        self.is_htmx && self.target.is_some() && !self.is_history_restore
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for HxRequest {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self::from_headers(&parts.headers))
+    }
+}
+
+
+/// How htmx swaps the response into its target (`HX-Reswap`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Swap {
+    InnerHtml,
+    OuterHtml,
+    BeforeBegin,
+    AfterBegin,
+    BeforeEnd,
+    AfterEnd,
+    Delete,
+    None,
+}
+
+impl fmt::Display for Swap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::InnerHtml => "innerHTML",
+            Self::OuterHtml => "outerHTML",
+            Self::BeforeBegin => "beforebegin",
+            Self::AfterBegin => "afterbegin",
+            Self::BeforeEnd => "beforeend",
+            Self::AfterEnd => "afterend",
+            Self::Delete => "delete",
+            Self::None => "none",
+        })
+    }
+}
+
+
+/// The headers that control what htmx does with a response. It can be returned alone, or with
+/// the rest of the response.
+///
+/// # Example
+///
+/// ```ignore
+/// (
+///     HxResponse::default()
+///         .push_url("/user/42")
+///         .trigger("user-created"),
+///     Html(markup.into_string()),
+/// )
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct HxResponse {
+    redirect: Option<String>,
+    triggers: Vec<String>,
+    push_url: Option<String>,
+    reswap: Option<Swap>,
+}
+
+impl HxResponse {
+    /// Make the browser go to this URL, with a full reload (`HX-Redirect`)
+    #[must_use]
+    pub fn redirect<U: Into<String>>(mut self, url: U) -> Self {
+        self.redirect = Some(url.into());
+        self
+    }
+
+    /// Trigger this event on the element that made the request (`HX-Trigger`). Can be called
+    /// several times to trigger several events.
+    #[must_use]
+    pub fn trigger<E: Into<String>>(mut self, event: E) -> Self {
+        self.triggers.push(event.into());
+        self
+    }
+
+    /// Push this URL in the history of the browser (`HX-Push-Url`)
+    #[must_use]
+    pub fn push_url<U: Into<String>>(mut self, url: U) -> Self {
+        self.push_url = Some(url.into());
+        self
+    }
+
+    /// Swap the response in another way than the one of the element that made the request
+    /// (`HX-Reswap`)
+    #[must_use]
+    pub const fn reswap(mut self, swap: Swap) -> Self {
+        self.reswap = Some(swap);
+        self
+    }
+}
+
+impl IntoResponseParts for HxResponse {
+    type Error = ServerError;
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        let triggers = (!self.triggers.is_empty()).then(|| self.triggers.join(", "));
+
+        for (name, value) in [
+            (HX_REDIRECT, self.redirect),
+            (HX_TRIGGER, triggers),
+            (HX_PUSH_URL, self.push_url),
+            (HX_RESWAP, self.reswap.map(|swap| swap.to_string())),
+        ] {
+            if let Some(value) = value {
+                res.headers_mut()
+                    .insert(name, HeaderValue::try_from(value)?);
+            }
+        }
+
+        Ok(res)
+    }
+}
+
+impl IntoResponse for HxResponse {
+    fn into_response(self) -> Response {
+        (self, ()).into_response()
+    }
+}
+
+
+/// A link that is swapped by htmx into `target`, and pushed in the history of the browser. It
+/// still works without JavaScript.
+///
+/// # Example
+///
+/// ```ignore
+/// html! {
+///     (htmx::link(&url_for!(pages::doc::page::render, page = "pages"), "#horizontal-content", "Pages"))
+/// }
+/// ```
+pub fn link<C: Render>(href: &str, target: &str, content: C) -> Markup {
+    html! {
+        a href=(href) hx-get=(href) hx-target=(target) hx-push-url="true" { (content) }
+    }
+}
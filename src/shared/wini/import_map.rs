@@ -0,0 +1,200 @@
+//! The import map of the pages, when their scripts are ES modules: what the browser loads for each
+//! specifier of an `import`.
+//!
+//! It maps:
+//! - the scripts of `src/` to their fingerprinted URL, with or without their extension, so that
+//!   the relative imports of the TypeScript files work once compiled
+//! - the paths of `tsconfig.json`, such as `~/utils/date`, and the imports from its `baseUrl`, to
+//!   the script they resolve to with `tsc`
+//! - the packages of `packages-files.toml` to their JavaScript file
+//!
+//! The integrity of the modules is in it too, since the browser checks the modules it imports
+//! with it.
+
+use {
+    super::{
+        JS_FILES,
+        assets::{MANIFEST, Manifest},
+        dependencies::url_of_path,
+        linked_file::{FileKind, LinkedFile},
+        packages_files::PACKAGES_FILES,
+        reloadable::Reloadable,
+        tsconfig::{TSCONFIG_PATHS, TsConfigPaths},
+    },
+    std::collections::{BTreeMap, HashMap},
+};
+
+
+/// The import map, computed when the files are loaded since it's the same for every page
+pub static IMPORT_MAP: Reloadable<ImportMap> = Reloadable::new(ImportMap::build);
+
+
+#[derive(Debug)]
+pub struct ImportMap {
+    /// The URL of each specifier. Sorted, so that the map is the same each time.
+    imports: BTreeMap<String, String>,
+    /// The integrity of each URL
+    integrity: BTreeMap<String, String>,
+    json: String,
+}
+
+impl ImportMap {
+    fn build() -> Self {
+        Self::new(
+            &MANIFEST.load(),
+            JS_FILES.load().keys(),
+            &TSCONFIG_PATHS,
+            &PACKAGES_FILES,
+        )
+    }
+
+    /// The import map of the scripts of `src/`, by URL, and of the files of the packages
+    pub fn new<'s, I: IntoIterator<Item = &'s String>>(
+        manifest: &Manifest,
+        scripts: I,
+        tsconfig_paths: &TsConfigPaths,
+        packages_files: &HashMap<String, Vec<LinkedFile>>,
+    ) -> Self {
+        let mut scripts = scripts.into_iter().collect::<Vec<_>>();
+        scripts.sort();
+
+        let mut imports = BTreeMap::new();
+        let mut integrity = BTreeMap::new();
+
+        for script in &scripts {
+            insert_script(&mut imports, script, manifest.url(script));
+            if let Some(hash) = manifest.integrity(script) {
+                integrity.insert(manifest.url(script).to_owned(), hash.to_owned());
+            }
+        }
+
+        // From the alias that applies last to the one that applies first, which overrides it
+        if let Some(base_url) = tsconfig_paths.base_url() {
+            insert_alias(
+                &mut imports,
+                manifest,
+                &scripts,
+                "*",
+                &url_of_path(base_url.join("*").as_path()),
+            );
+        }
+        for pattern in tsconfig_paths.patterns().iter().rev() {
+            if let Some(prefix) = pattern.key.strip_suffix("/*") &&
+                let Some(directory) = pattern.targets.first().and_then(|target| {
+                    url_of_path(target)
+                        .strip_suffix("/*")
+                        .map(ToOwned::to_owned)
+                })
+            {
+                // The other files of the directory, such as the public ones
+                imports.insert(format!("{prefix}/"), format!("{directory}/"));
+            }
+
+            // The first target in which a script exists wins, like with `tsc`
+            for target in pattern.targets.iter().rev() {
+                insert_alias(
+                    &mut imports,
+                    manifest,
+                    &scripts,
+                    &pattern.key,
+                    &url_of_path(target),
+                );
+            }
+        }
+
+        for (package, files) in packages_files {
+            let script = files
+                .iter()
+                .find(|file| matches!(file.kind(), FileKind::Script | FileKind::Module));
+
+            if let Some(script) = script {
+                let url = manifest.url(&script.url).to_owned();
+                if let Some(hash) = script.integrity() {
+                    integrity.insert(url.clone(), hash);
+                }
+                imports.insert(package.clone(), url);
+            }
+        }
+
+        let json = serde_json::json!({ "imports": imports, "integrity": integrity })
+            .to_string()
+            // It's in a `<script>`, that mustn't be closed
+            .replace("</", "<\\/");
+
+        Self {
+            imports,
+            integrity,
+            json,
+        }
+    }
+
+    /// The URL to which a specifier is mapped
+    pub fn get(&self, specifier: &str) -> Option<&str> {
+        self.imports.get(specifier).map(String::as_str)
+    }
+
+    /// The integrity of a module, by its URL
+    pub fn integrity(&self, url: &str) -> Option<&str> {
+        self.integrity.get(url).map(String::as_str)
+    }
+
+    /// The content of the `<script type="importmap">`
+    pub fn json(&self) -> &str {
+        &self.json
+    }
+}
+
+/// Map the scripts that match `target`, such as `/src/utils/*`, to the specifiers of `key`, such as
+/// `~/utils/*`, in which the `*` is replaced by what it matched
+fn insert_alias(
+    imports: &mut BTreeMap<String, String>,
+    manifest: &Manifest,
+    scripts: &[&String],
+    key: &str,
+    target: &str,
+) {
+    let is_exact = !key.contains('*');
+    // The scripts are compared without their extension
+    let target = ["js", "ts"]
+        .into_iter()
+        .find_map(|extension| target.strip_suffix(&format!(".{extension}")))
+        .unwrap_or(target);
+    let (key_prefix, key_suffix) = key.split_once('*').unwrap_or((key, ""));
+    let (target_prefix, target_suffix) = target.split_once('*').unwrap_or((target, ""));
+
+    for script in scripts {
+        let without_extension = script.strip_suffix(".js").unwrap_or(script);
+        // A directory is imported with its `index`
+        let directory = without_extension.strip_suffix("/index");
+
+        for path in [Some(without_extension), directory].into_iter().flatten() {
+            let Some(matched) = path
+                .strip_prefix(target_prefix)
+                .and_then(|rest| rest.strip_suffix(target_suffix))
+                .filter(|matched| !is_exact || matched.is_empty())
+            else {
+                continue;
+            };
+
+            let specifier = format!("{key_prefix}{matched}{key_suffix}");
+            if !is_exact && key_suffix.is_empty() && path == without_extension {
+                insert_script(imports, &format!("{specifier}.js"), manifest.url(script));
+            } else {
+                imports.insert(specifier, manifest.url(script).to_owned());
+            }
+        }
+    }
+}
+
+/// Map a script, written with its `.js` extension, also without it and with `.ts`
+fn insert_script(imports: &mut BTreeMap<String, String>, script: &str, url: &str) {
+    let without_extension = script.strip_suffix(".js").unwrap_or(script);
+
+    for specifier in [
+        script.to_owned(),
+        without_extension.to_owned(),
+        format!("{without_extension}.ts"),
+    ] {
+        imports.insert(specifier, url.to_owned());
+    }
+}
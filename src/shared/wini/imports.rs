@@ -0,0 +1,278 @@
+//! The modules imported by a JavaScript or TypeScript file, found by reading its tokens, so that
+//! the imports in comments and strings are ignored.
+//!
+//! These imports are found:
+//! - `import "x"`, `import x from "x"`, `import { a, b } from "x"`, `import * as x from "x"`, on
+//!   one line or more, with or without a semicolon
+//! - `export * from "x"`, `export * as x from "x"`, `export { a } from "x"`
+//! - `import("x")`, when the module is a string
+//! - `require("x")`
+//!
+//! The imports of types only (`import type`, `export type`), that don't exist in JavaScript, are
+//! ignored.
+
+
+/// The modules imported by `source`, in their order
+///
+/// # Example
+///
+/// ```ignore
+/// assert_eq!(
+///     imports("import { a } from './a';\nconst b = await import('./b');"),
+///     ["./a", "./b"]
+/// );
+/// ```
+pub fn imports(source: &str) -> Vec<String> {
+    let tokens = tokens(source);
+    let mut imports = Vec::new();
+
+    for (index, token) in tokens.iter().enumerate() {
+        // `object.import(...)` and `object.require(...)` aren't imports
+        if index > 0 && tokens.get(index - 1) == Some(&Token::Punct('.')) {
+            continue;
+        }
+
+        let rest = tokens.get(index + 1..).unwrap_or_default();
+        let import = match token {
+            Token::Ident("import") => static_import(rest).or_else(|| call(rest)),
+            Token::Ident("export") => reexport(rest),
+            Token::Ident("require") => call(rest),
+            _ => None,
+        };
+
+        imports.extend(import.map(ToOwned::to_owned));
+    }
+
+    imports
+}
+
+
+#[derive(Debug, PartialEq)]
+enum Token<'s> {
+    /// An identifier, a keyword or a number
+    Ident(&'s str),
+    /// A string, without its quotes
+    Str(String),
+    /// A template literal. Its content isn't needed.
+    Template,
+    Punct(char),
+}
+
+/// The keywords after which a `/` starts a regular expression, and not a division
+const KEYWORDS_BEFORE_EXPRESSION: [&str; 14] = [
+    "return",
+    "typeof",
+    "instanceof",
+    "in",
+    "of",
+    "new",
+    "delete",
+    "void",
+    "throw",
+    "case",
+    "do",
+    "else",
+    "yield",
+    "await",
+];
+
+/// What an opening brace closes into
+enum Brace {
+    Block,
+    /// The `${` of a template literal, after which the template continues
+    TemplateExpression,
+}
+
+fn tokens(source: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut braces = Vec::new();
+    let mut chars = source.char_indices().peekable();
+
+    while let Some((start, char)) = chars.next() {
+        match char {
+            '/' if chars.peek().is_some_and(|(_, next)| *next == '/') => {
+                chars.by_ref().find(|(_, char)| *char == '\n');
+            },
+            '/' if chars.peek().is_some_and(|(_, next)| *next == '*') => {
+                chars.next();
+                let mut previous = ' ';
+                for (_, char) in chars.by_ref() {
+                    if previous == '*' && char == '/' {
+                        break;
+                    }
+                    previous = char;
+                }
+            },
+            '/' if is_regex_allowed(tokens.last()) => {
+                skip_regex(&mut chars);
+                // A regular expression is a value, like a template
+                tokens.push(Token::Template);
+            },
+            '"' | '\'' => {
+                let mut string = String::new();
+                while let Some((_, string_char)) = chars.next() {
+                    match string_char {
+                        '\\' => string.extend(chars.next().map(|(_, char)| char)),
+                        '\n' => break,
+                        _ if string_char == char => break,
+                        _ => string.push(string_char),
+                    }
+                }
+                tokens.push(Token::Str(string));
+            },
+            '`' => {
+                if skip_template(&mut chars) {
+                    braces.push(Brace::TemplateExpression);
+                }
+                tokens.push(Token::Template);
+            },
+            '{' => {
+                braces.push(Brace::Block);
+                tokens.push(Token::Punct('{'));
+            },
+            '}' => {
+                match braces.pop() {
+                    Some(Brace::TemplateExpression) => {
+                        if skip_template(&mut chars) {
+                            braces.push(Brace::TemplateExpression);
+                        }
+                        tokens.push(Token::Template);
+                    },
+                    _ => tokens.push(Token::Punct('}')),
+                }
+            },
+            char if is_ident_char(char) => {
+                let mut end = start + char.len_utf8();
+                while let Some((index, char)) = chars.next_if(|(_, char)| is_ident_char(*char)) {
+                    end = index + char.len_utf8();
+                }
+                tokens.push(Token::Ident(source.get(start..end).unwrap_or_default()));
+            },
+            char if char.is_whitespace() => {},
+            char => tokens.push(Token::Punct(char)),
+        }
+    }
+
+    tokens
+}
+
+fn is_ident_char(char: char) -> bool {
+    char.is_alphanumeric() || matches!(char, '_' | '$')
+}
+
+/// Whether a `/` after this token starts a regular expression
+fn is_regex_allowed(previous: Option<&Token>) -> bool {
+    match previous {
+        None => true,
+        Some(Token::Punct(char)) => !matches!(char, ')' | ']' | '}'),
+        Some(Token::Ident(ident)) => KEYWORDS_BEFORE_EXPRESSION.contains(ident),
+        Some(Token::Str(_) | Token::Template) => false,
+    }
+}
+
+fn skip_regex<I: Iterator<Item = (usize, char)>>(chars: &mut I) {
+    let mut is_in_class = false;
+
+    while let Some((_, char)) = chars.next() {
+        match char {
+            '\\' => {
+                chars.next();
+            },
+            '[' => is_in_class = true,
+            ']' => is_in_class = false,
+            '/' if !is_in_class => break,
+            '\n' => break,
+            _ => {},
+        }
+    }
+}
+
+/// Skip the content of a template literal, until its end or the start of an expression (`${`).
+/// Returns whether an expression starts.
+fn skip_template<I: Iterator<Item = (usize, char)>>(chars: &mut std::iter::Peekable<I>) -> bool {
+    while let Some((_, char)) = chars.next() {
+        match char {
+            '\\' => {
+                chars.next();
+            },
+            '`' => return false,
+            '$' if chars.next_if(|(_, next)| *next == '{').is_some() => return true,
+            _ => {},
+        }
+    }
+
+    false
+}
+
+
+/// The module of `import ... from "x"` or `import "x"`, after `import`
+fn static_import<'t>(tokens: &'t [Token<'_>]) -> Option<&'t str> {
+    let mut tokens = tokens.iter().peekable();
+
+    if let Some(Token::Str(module)) = tokens.peek() {
+        return Some(module);
+    }
+
+    // `import type { A } from "x"`, but not `import type from "x"`, that imports `type`
+    let is_type_only = tokens.peek() == Some(&&Token::Ident("type")) &&
+        !matches!(
+            tokens.clone().nth(1),
+            Some(Token::Ident("from") | Token::Punct(','))
+        );
+    if is_type_only {
+        tokens.next();
+    }
+
+    let module = from_clause(tokens)?;
+    (!is_type_only).then_some(module)
+}
+
+/// The module of `export * from "x"` or `export { a } from "x"`, after `export`
+fn reexport<'t>(tokens: &'t [Token<'_>]) -> Option<&'t str> {
+    let mut tokens = tokens.iter().peekable();
+
+    if tokens.next_if_eq(&&Token::Ident("type")).is_some() {
+        return None;
+    }
+
+    match tokens.peek() {
+        Some(Token::Punct('*' | '{')) => from_clause(tokens),
+        _ => None,
+    }
+}
+
+/// The module of the imported or exported names followed by `from "x"`
+fn from_clause<'t, 's: 't, I>(mut tokens: std::iter::Peekable<I>) -> Option<&'t str>
+where
+    I: Iterator<Item = &'t Token<'s>>,
+{
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Ident("from") => {
+                if let Some(Token::Str(module)) = tokens.peek() {
+                    return Some(module);
+                }
+            },
+            Token::Punct('{') => {
+                tokens.by_ref().find(|token| **token == Token::Punct('}'))?;
+            },
+            Token::Ident(_) | Token::Punct(',' | '*') => {},
+            _ => return None,
+        }
+    }
+
+    None
+}
+
+/// The module of `("x")`, after `import` or `require`
+fn call<'t>(tokens: &'t [Token<'_>]) -> Option<&'t str> {
+    match tokens {
+        [
+            Token::Punct('('),
+            Token::Str(module),
+            Token::Punct(')' | ','),
+            ..,
+        ] => Some(module),
+        _ => None,
+    }
+}
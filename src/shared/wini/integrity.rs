@@ -0,0 +1,112 @@
+//! The Subresource Integrity of the linked files: the hash, in their `integrity` attribute, that the
+//! browser checks before running a script or applying a style sheet.
+//!
+//! The hashes of the files of the website are computed when they are loaded, and the ones of the
+//! files of other websites, such as a package from a CDN, are pinned in `packages-files.toml`.
+//! `verify_packages` checks the pinned hashes against the copies of the files in `node_modules`.
+
+use {
+    super::{linked_file::FileKind, packages_files},
+    base64::{Engine, engine::general_purpose::STANDARD},
+    sha2::{Digest, Sha256, Sha384, Sha512},
+};
+
+
+/// The integrity of `content`, such as `sha384-oqVuAfXRKap7fdgcCY5uykM6+R9GqQ8K/uxy9rx7HNQlGYl1kPzQho1wx4JwY8wC`
+pub fn integrity(content: &[u8]) -> String {
+    format!("sha384-{}", STANDARD.encode(Sha384::digest(content)))
+}
+
+/// Whether `content` has one of the hashes of `integrity`. Like the browsers, the hashes of unknown
+/// algorithms are ignored, so it doesn't match if none is known, and only the ones of the strongest
+/// algorithm are checked.
+pub fn matches(integrity: &str, content: &[u8]) -> bool {
+    let hashes = integrity
+        .split_whitespace()
+        .filter_map(|hash| {
+            // The options after `?` are reserved
+            let hash = hash.split('?').next().unwrap_or(hash);
+            let (algorithm, digest) = hash.split_once('-')?;
+            let strength = match algorithm {
+                "sha256" => 0,
+                "sha384" => 1,
+                "sha512" => 2,
+                _ => return None,
+            };
+            Some((strength, algorithm, digest))
+        })
+        .collect::<Vec<_>>();
+
+    let Some(strongest) = hashes.iter().map(|(strength, ..)| *strength).max() else {
+        return false;
+    };
+
+    hashes
+        .iter()
+        .filter(|(strength, ..)| *strength == strongest)
+        .any(|(_, algorithm, digest)| {
+            match *algorithm {
+                "sha256" => *digest == STANDARD.encode(Sha256::digest(content)),
+                "sha384" => *digest == STANDARD.encode(Sha384::digest(content)),
+                _ => *digest == STANDARD.encode(Sha512::digest(content)),
+            }
+        })
+}
+
+
+/// Check the files of `packages-files.toml`: the scripts and style sheets of other websites must
+/// have a pinned integrity, and the pinned hashes must match the copies of the files. Returns
+/// whether everything is right, after logging what isn't.
+pub fn verify_packages() -> bool {
+    let mut is_valid = true;
+
+    for file in packages_files::vendored_files() {
+        let url = &file.linked_file.url;
+        let needs_integrity = !file.linked_file.is_local() &&
+            matches!(
+                file.linked_file.kind(),
+                FileKind::Script | FileKind::Module | FileKind::Stylesheet
+            );
+
+        let Some(integrity) = &file.linked_file.integrity else {
+            if needs_integrity {
+                log::error!("`{url}` of `{}` has no pinned integrity", file.package);
+                is_valid = false;
+            }
+            continue;
+        };
+
+        let Some(path) = &file.path else {
+            log::error!(
+                "`{url}` of `{}` has no vendored copy to check its integrity against: set \
+                 `vendored` to its path in the package",
+                file.package
+            );
+            is_valid = false;
+            continue;
+        };
+
+        match std::fs::read(path) {
+            Ok(content) if matches(integrity, &content) => {
+                log::info!("`{url}` matches `{}`", path.display());
+            },
+            Ok(content) => {
+                log::error!(
+                    "`{url}` doesn't match `{}`: its integrity is `{}`",
+                    path.display(),
+                    self::integrity(&content)
+                );
+                is_valid = false;
+            },
+            Err(err) => {
+                log::error!(
+                    "Couldn't read `{}`, the copy of `{url}`: {err}",
+                    path.display()
+                );
+                is_valid = false;
+            },
+        }
+    }
+
+    is_valid
+}
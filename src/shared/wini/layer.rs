@@ -1,9 +1,11 @@
 use {
+    super::{htmx::HxRequest, linked_file::LinkedFile},
     axum::{extract::Request, response::Response},
     derive_builder::Builder,
+    hyper::{HeaderMap, header::CONTENT_TYPE},
     std::{
         borrow::Cow,
-        collections::{HashMap, HashSet},
+        collections::{BTreeSet, HashMap},
         pin::Pin,
         sync::Arc,
         task::{Context, Poll},
@@ -12,7 +14,48 @@ use {
 };
 
 pub type Tags = HashMap<&'static str, Cow<'static, str>>;
-pub type Files = HashSet<Cow<'static, str>>;
+/// The files linked by a page, sorted so that the page is the same each time
+pub type Files = BTreeSet<LinkedFile>;
+
+/// In the extensions of a response that isn't a full page, such as a fragment of HTML or a
+/// redirection: the layouts and the template don't apply to it.
+#[derive(Debug, Clone, Copy)]
+pub struct Fragment;
+
+/// The headers on which the rendering of a page depends, to send in `Vary`
+pub const VARY_HEADERS: &str = "HX-Request, HX-Target";
+
+/// Whether a request is made by htmx to swap the content of a page into an element, such as
+/// `#horizontal-content`: it only wants the page, without its layouts and template.
+///
+/// The requests that restore the history of the browser, and the boosted ones that don't target
+/// an element, want the full page.
+pub fn is_fragment_request(headers: &HeaderMap) -> bool {
+    HxRequest::from_headers(headers).is_fragment()
+}
+
+/// Whether the layouts and the template apply to a response: only to HTML that isn't a
+/// [`Fragment`], a redirection, or the answer to a fragment request (see [`is_fragment_request`]).
+///
+/// JSON, binary files and the other kinds of content are left untouched.
+pub fn is_full_page(resp: &Response, is_fragment_request: bool) -> bool {
+    if is_fragment_request ||
+        resp.status().is_redirection() ||
+        resp.extensions().get::<Fragment>().is_some()
+    {
+        return false;
+    }
+
+    is_html(resp.headers())
+}
+
+/// Whether the `Content-Type` of a response is HTML
+pub fn is_html(headers: &HeaderMap) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("text/html"))
+}
 
 #[derive(Clone, Builder)]
 pub struct MetaLayer {
@@ -1,4 +1,4 @@
-//! Traits helpers used to know if a type implements a trait.
+//! Traits helpers used to know if a type implements a trait, for the state `S` of the router.
 //! Used in `#[layout]`s
 
 use {
@@ -14,43 +14,43 @@ use {
 
 
 /// Check if it's [`axum::extract::FromRequestParts`]
-pub trait IsFromRequestParts {
+pub trait IsFromRequestParts<S> {
     const IS_FROM_REQUEST_PARTS: bool = false;
 }
 
-impl<T> IsFromRequestParts for T {
+impl<T, S> IsFromRequestParts<S> for T {
     default const IS_FROM_REQUEST_PARTS: bool = false;
 }
 
-impl<T: FromRequestParts<()>> IsFromRequestParts for T {
+impl<T: FromRequestParts<S>, S> IsFromRequestParts<S> for T {
     const IS_FROM_REQUEST_PARTS: bool = true;
 }
 
 
 /// Check if it's [`crate::shared::wini::response::FromResponseBody`]
-pub trait IsFromResponseBody {
+pub trait IsFromResponseBody<S> {
     const IS_FROM_RESPONSE_BODY: bool = false;
 }
 
-impl<T> IsFromResponseBody for T {
+impl<T, S> IsFromResponseBody<S> for T {
     default const IS_FROM_RESPONSE_BODY: bool = false;
 }
 
-impl<T: FromResponseBody<()>> IsFromResponseBody for T {
+impl<T: FromResponseBody<S>, S> IsFromResponseBody<S> for T {
     const IS_FROM_RESPONSE_BODY: bool = true;
 }
 
 
 /// Check if it's [`crate::shared::wini::response::FromResponseParts`]
-pub trait IsFromResponseParts {
+pub trait IsFromResponseParts<S> {
     const IS_FROM_RESPONSE_PARTS: bool = false;
 }
 
-impl<T> IsFromResponseParts for T {
+impl<T, S> IsFromResponseParts<S> for T {
     default const IS_FROM_RESPONSE_PARTS: bool = false;
 }
 
-impl<T: FromResponseParts<()>> IsFromResponseParts for T {
+impl<T: FromResponseParts<S>, S> IsFromResponseParts<S> for T {
     const IS_FROM_RESPONSE_PARTS: bool = true;
 }
 
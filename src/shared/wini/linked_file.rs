@@ -0,0 +1,307 @@
+//! The files linked by the pages, with how they are linked: a script, a style sheet, or a file
+//! preloaded, with the attributes of its tag, in the `<head>` or at the end of the `<body>`.
+//!
+//! They are set by the files next to the pages, layouts and components, their `file(...)`
+//! attributes, and the entries of `packages-files.toml`.
+//!
+//! The scripts and style sheets are linked with their Subresource Integrity: the one computed when
+//! the files of the website are loaded, or the one pinned for the files of other websites.
+//!
+//! The components only carry strings, in [`maud::Markup::linked_files`], so the files are stored
+//! there with [`LinkedFile::encode`]: as their path when they have no options, or else as JSON.
+
+use {
+    super::{
+        JS_FILES,
+        assets::MANIFEST,
+        bundle::{self, BUNDLES},
+        config::SERVER_CONFIG,
+    },
+    maud::{Markup, html},
+    serde::{Deserialize, Serialize},
+    std::path::Path,
+};
+
+
+/// A file linked by a page
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LinkedFile {
+    /// Its URL, such as `/src/pages/doc/style.css` or `https://cdn.example.com/lib.js`
+    #[serde(alias = "file")]
+    pub url: String,
+    /// Guessed from the extension of the file if it isn't set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<FileKind>,
+    #[serde(skip_serializing_if = "is_default")]
+    pub loading: Loading,
+    /// The media query of a style sheet or of a preloaded file, such as `print`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub media: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crossorigin: Option<CrossOrigin>,
+    /// The hash of the content of the file, such as `sha384-...`, pinned for the files of other
+    /// websites. The one of the files of the website is computed if it isn't set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub integrity: Option<String>,
+    /// The `fetchpriority` of the tag
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<Priority>,
+    #[serde(skip_serializing_if = "is_default")]
+    pub placement: Placement,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileKind {
+    /// `<script>`, or `<script type="module">` for the scripts of `src/` if they are ES modules
+    Script,
+    /// `<script type="module">`
+    Module,
+    /// `<link rel="stylesheet">`
+    Stylesheet,
+    /// `<link rel="preload">`, with the type of content guessed from the extension of the file
+    Preload,
+    /// `<link rel="preload" as="font" crossorigin>`
+    Font,
+}
+
+/// When a script is run
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Loading {
+    /// Once the document is parsed, in the order of the scripts
+    #[default]
+    Defer,
+    /// As soon as it's downloaded, in any order
+    Async,
+    /// When it's reached, before the rest of the document is parsed
+    Blocking,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CrossOrigin {
+    Anonymous,
+    UseCredentials,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    High,
+    Low,
+    Auto,
+}
+
+/// Where the tag of a file is in the document
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Placement {
+    #[default]
+    Head,
+    /// At the end of the `<body>`, after the deferred components
+    Body,
+}
+
+
+impl LinkedFile {
+    /// A file linked with the default options of its kind. The paths relative to the root of the
+    /// project, like `src/pages/doc/style.css`, are served at `/src/pages/doc/style.css`.
+    pub fn new<S: Into<String>>(url: S) -> Self {
+        Self {
+            url: normalize_url(url.into()),
+            ..Self::default()
+        }
+    }
+
+    /// A file stored in [`maud::Markup::linked_files`] with [`Self::encode`]
+    pub fn decode(encoded: &str) -> Self {
+        if encoded.starts_with('{') {
+            match serde_json::from_str::<Self>(encoded) {
+                Ok(file) => {
+                    return Self {
+                        url: normalize_url(file.url),
+                        ..file
+                    };
+                },
+                Err(err) => log::warn!("Invalid linked file `{encoded}`: {err}"),
+            }
+        }
+
+        Self::new(encoded)
+    }
+
+    /// The file as a string, to store it in [`maud::Markup::linked_files`]
+    pub fn encode(&self) -> String {
+        if self.has_options() {
+            serde_json::to_string(self).unwrap_or_else(|_| self.url.clone())
+        } else {
+            self.url.clone()
+        }
+    }
+
+    /// Whether the file is linked differently than the other files of its kind
+    pub fn has_options(&self) -> bool {
+        *self != Self::new(self.url.clone())
+    }
+
+    pub fn kind(&self) -> FileKind {
+        self.kind.unwrap_or_else(|| {
+            match self.extension() {
+                Some("css") => FileKind::Stylesheet,
+                Some("js" | "mjs") => FileKind::Script,
+                Some("woff2" | "woff" | "ttf" | "otf") => FileKind::Font,
+                _ => FileKind::Preload,
+            }
+        })
+    }
+
+    /// The pinned integrity of the file, or else the one computed when it was loaded or bundled
+    pub fn integrity(&self) -> Option<String> {
+        if let Some(integrity) = &self.integrity {
+            return Some(integrity.clone());
+        }
+
+        if self.url.starts_with(bundle::ROUTE) {
+            BUNDLES
+                .load()
+                .built(&self.url)
+                .map(|bundle| bundle.integrity.clone())
+        } else {
+            MANIFEST.load().integrity(&self.url).map(ToOwned::to_owned)
+        }
+    }
+
+    /// Whether it's a file of the website, and not of another one
+    pub fn is_local(&self) -> bool {
+        !self.url.contains("://")
+    }
+
+    fn extension(&self) -> Option<&str> {
+        Path::new(&self.url).extension()?.to_str()
+    }
+
+    /// Whether the script is an ES module
+    fn is_module(&self) -> bool {
+        match self.kind() {
+            FileKind::Module => true,
+            FileKind::Script => {
+                SERVER_CONFIG.scripts.modules && JS_FILES.load().contains_key(&self.url)
+            },
+            _ => false,
+        }
+    }
+
+    /// The type of content of a preloaded file, in the `as` attribute
+    fn preload_as(&self) -> &'static str {
+        match self.kind() {
+            FileKind::Font => "font",
+            _ => {
+                match self.extension() {
+                    Some("css") => "style",
+                    Some("js" | "mjs") => "script",
+                    Some("woff2" | "woff" | "ttf" | "otf") => "font",
+                    Some("png" | "jpg" | "jpeg" | "gif" | "webp" | "avif" | "svg" | "ico") => {
+                        "image"
+                    },
+                    _ => "fetch",
+                }
+            },
+        }
+    }
+
+    /// The tag that links the file, with its fingerprinted URL
+    pub fn to_html(&self, nonce: Option<&str>) -> Markup {
+        let manifest = MANIFEST.load();
+        let url = manifest.url(&self.url);
+        let integrity = self.integrity();
+        // The integrity of the files of other websites can only be checked in CORS mode
+        let crossorigin = match self.crossorigin {
+            None if integrity.is_some() && !self.is_local() => Some(CrossOrigin::Anonymous),
+            crossorigin => crossorigin,
+        }
+        .map(CrossOrigin::as_str);
+        let priority = self.priority.map(Priority::as_str);
+
+        html! {
+            @match self.kind() {
+                FileKind::Stylesheet => {
+                    link
+                        rel="stylesheet"
+                        href=(url)
+                        media=[self.media.as_deref()]
+                        integrity=[integrity.as_deref()]
+                        crossorigin=[crossorigin]
+                        fetchpriority=[priority]
+                        nonce=[nonce];
+                },
+                FileKind::Script | FileKind::Module => {
+                    @let is_module = self.is_module();
+                    script
+                        type=[is_module.then_some("module")]
+                        src=(url)
+                        // The modules are deferred
+                        defer[!is_module && self.loading == Loading::Defer]
+                        async[self.loading == Loading::Async]
+                        integrity=[integrity.as_deref()]
+                        crossorigin=[crossorigin]
+                        fetchpriority=[priority]
+                        nonce=[nonce] {}
+                },
+                FileKind::Preload | FileKind::Font => {
+                    @let preload_as = self.preload_as();
+                    // The fonts are always fetched in CORS mode
+                    @let crossorigin = match (crossorigin, preload_as) {
+                        (None, "font") => Some(CrossOrigin::Anonymous.as_str()),
+                        _ => crossorigin,
+                    };
+                    link
+                        rel="preload"
+                        href=(url)
+                        as=(preload_as)
+                        media=[self.media.as_deref()]
+                        integrity=[integrity.as_deref()]
+                        crossorigin=[crossorigin]
+                        fetchpriority=[priority]
+                        nonce=[nonce];
+                },
+            }
+        }
+    }
+}
+
+impl CrossOrigin {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Anonymous => "anonymous",
+            Self::UseCredentials => "use-credentials",
+        }
+    }
+}
+
+impl Priority {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::High => "high",
+            Self::Low => "low",
+            Self::Auto => "auto",
+        }
+    }
+}
+
+fn normalize_url(url: String) -> String {
+    if url.starts_with('/') || url.contains("://") {
+        url
+    } else {
+        format!("/{url}")
+    }
+}
+
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
@@ -0,0 +1,274 @@
+//! Metrics of the server, exposed in the Prometheus text format.
+//!
+//! See: <https://prometheus.io/docs/instrumenting/exposition_formats/>
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    pin::Pin,
+    sync::{Mutex, PoisonError},
+    time::Duration,
+};
+
+/// Default buckets of the histograms, in seconds
+const DEFAULT_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+type LabelValues = Vec<String>;
+
+/// A family of counters, one per combination of label values
+pub struct CounterVec {
+    name: &'static str,
+    help: &'static str,
+    labels: &'static [&'static str],
+    values: Mutex<BTreeMap<LabelValues, u64>>,
+}
+
+impl CounterVec {
+    pub const fn new(
+        name: &'static str,
+        help: &'static str,
+        labels: &'static [&'static str],
+    ) -> Self {
+        Self {
+            name,
+            help,
+            labels,
+            values: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Increment the counter associated to these label values
+    pub fn inc(&self, label_values: &[&str]) {
+        let mut values = self.values.lock().unwrap_or_else(PoisonError::into_inner);
+        *values
+            .entry(label_values.iter().map(|v| (*v).to_owned()).collect())
+            .or_default() += 1;
+    }
+
+    /// Append the counters in the Prometheus text format
+    pub fn encode(&self, out: &mut String) {
+        let values = self.values.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let _ = writeln!(out, "# HELP {} {}", self.name, self.help);
+        let _ = writeln!(out, "# TYPE {} counter", self.name);
+        for (label_values, value) in &*values {
+            let _ = writeln!(
+                out,
+                "{}{} {value}",
+                self.name,
+                format_labels(self.labels, label_values, None)
+            );
+        }
+    }
+}
+
+
+#[derive(Default)]
+struct HistogramData {
+    /// Cumulative count per bucket
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+/// A family of histograms, one per combination of label values
+pub struct HistogramVec {
+    name: &'static str,
+    help: &'static str,
+    labels: &'static [&'static str],
+    values: Mutex<BTreeMap<LabelValues, HistogramData>>,
+}
+
+impl HistogramVec {
+    pub const fn new(
+        name: &'static str,
+        help: &'static str,
+        labels: &'static [&'static str],
+    ) -> Self {
+        Self {
+            name,
+            help,
+            labels,
+            values: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Record a duration in the histogram associated to these label values
+    pub fn observe(&self, label_values: &[&str], duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        let mut values = self.values.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let data = values
+            .entry(label_values.iter().map(|v| (*v).to_owned()).collect())
+            .or_insert_with(|| {
+                HistogramData {
+                    buckets: vec![0; DEFAULT_BUCKETS.len()],
+                    ..Default::default()
+                }
+            });
+
+        for (bucket, upper_bound) in data.buckets.iter_mut().zip(DEFAULT_BUCKETS) {
+            if seconds <= upper_bound {
+                *bucket += 1;
+            }
+        }
+        data.sum += seconds;
+        data.count += 1;
+    }
+
+    /// Append the histograms in the Prometheus text format
+    pub fn encode(&self, out: &mut String) {
+        let values = self.values.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let _ = writeln!(out, "# HELP {} {}", self.name, self.help);
+        let _ = writeln!(out, "# TYPE {} histogram", self.name);
+        for (label_values, data) in &*values {
+            for (bucket, upper_bound) in data.buckets.iter().zip(DEFAULT_BUCKETS) {
+                let _ = writeln!(
+                    out,
+                    "{}_bucket{} {bucket}",
+                    self.name,
+                    format_labels(self.labels, label_values, Some(&upper_bound.to_string()))
+                );
+            }
+            let _ = writeln!(
+                out,
+                "{}_bucket{} {}",
+                self.name,
+                format_labels(self.labels, label_values, Some("+Inf")),
+                data.count
+            );
+            let labels = format_labels(self.labels, label_values, None);
+            let _ = writeln!(out, "{}_sum{labels} {}", self.name, data.sum);
+            let _ = writeln!(out, "{}_count{labels} {}", self.name, data.count);
+        }
+    }
+}
+
+
+/// Format labels as `{name="value",...}`, with an optional `le` label for histogram buckets
+fn format_labels(names: &[&str], values: &[String], le: Option<&str>) -> String {
+    let mut labels = names
+        .iter()
+        .zip(values)
+        .map(|(name, value)| format!("{name}=\"{}\"", escape_label_value(value)))
+        .collect::<Vec<String>>();
+
+    if let Some(le) = le {
+        labels.push(format!("le=\"{le}\""));
+    }
+
+    if labels.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", labels.join(","))
+    }
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+
+/// Number of HTTP requests handled
+pub static HTTP_REQUESTS_TOTAL: CounterVec = CounterVec::new(
+    "wini_http_requests_total",
+    "Number of HTTP requests handled.",
+    &["method", "route", "status"],
+);
+
+/// Latency of the HTTP requests
+pub static HTTP_REQUEST_DURATION_SECONDS: HistogramVec = HistogramVec::new(
+    "wini_http_request_duration_seconds",
+    "Latency of the HTTP requests.",
+    &["method", "route"],
+);
+
+/// Time spent rendering `#[page]`s and `#[layout]`s
+pub static RENDER_DURATION_SECONDS: HistogramVec = HistogramVec::new(
+    "wini_render_duration_seconds",
+    "Time spent rendering pages and layouts.",
+    &["kind", "function"],
+);
+
+/// Number of times a cron has been run
+pub static CRON_RUNS_TOTAL: CounterVec = CounterVec::new(
+    "wini_cron_runs_total",
+    "Number of times a cron has been run.",
+    &["cron"],
+);
+
+/// Number of times a cron has failed
+pub static CRON_FAILURES_TOTAL: CounterVec = CounterVec::new(
+    "wini_cron_failures_total",
+    "Number of times a cron has failed.",
+    &["cron"],
+);
+
+
+/// The hits and misses of a cached function
+pub type CacheStats = (Option<u64>, Option<u64>);
+pub type CacheStatsFuture = Pin<Box<dyn Future<Output = CacheStats> + Send>>;
+
+/// The functions marked with `#[init_cache]`, with a way to retrieve their cache statistics
+static FUNCTION_CACHES: Mutex<Vec<(&'static str, fn() -> CacheStatsFuture)>> =
+    Mutex::new(Vec::new());
+
+/// Register a `#[cached]` function so that its hits and misses are exposed.
+///
+/// This is used by `#[init_cache]`, when the functions are cached in the environment.
+pub fn register_function_cache(function: &'static str, stats: fn() -> CacheStatsFuture) {
+    FUNCTION_CACHES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push((function, stats));
+}
+
+
+/// Encode all the metrics in the Prometheus text format
+pub async fn encode() -> String {
+    let mut out = String::new();
+
+    HTTP_REQUESTS_TOTAL.encode(&mut out);
+    HTTP_REQUEST_DURATION_SECONDS.encode(&mut out);
+    RENDER_DURATION_SECONDS.encode(&mut out);
+    CRON_RUNS_TOTAL.encode(&mut out);
+    CRON_FAILURES_TOTAL.encode(&mut out);
+
+    let function_caches = FUNCTION_CACHES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+
+    let mut hits = String::new();
+    let mut misses = String::new();
+    for (function, stats) in function_caches {
+        let (maybe_hits, maybe_misses) = stats().await;
+        let labels = format_labels(&["function"], &[function.to_owned()], None);
+
+        if let Some(value) = maybe_hits {
+            let _ = writeln!(hits, "wini_function_cache_hits_total{labels} {value}");
+        }
+        if let Some(value) = maybe_misses {
+            let _ = writeln!(misses, "wini_function_cache_misses_total{labels} {value}");
+        }
+    }
+
+    out.push_str(
+        "# HELP wini_function_cache_hits_total Number of hits of `#[cached]` functions.\n\
+         # TYPE wini_function_cache_hits_total counter\n",
+    );
+    out.push_str(&hits);
+    out.push_str(
+        "# HELP wini_function_cache_misses_total Number of misses of `#[cached]` functions.\n\
+         # TYPE wini_function_cache_misses_total counter\n",
+    );
+    out.push_str(&misses);
+
+    out
+}
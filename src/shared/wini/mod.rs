@@ -3,6 +3,7 @@ use {
     dotenvy::dotenv,
     env::EnvType,
     err::ExitWithMessageIfErr,
+    reloadable::Reloadable,
     std::{collections::HashMap, str::FromStr, sync::LazyLock},
 };
 
@@ -10,34 +11,33 @@ type FileContent = String;
 type FileName = String;
 
 /// The list of all the public endpoints <=> all the files in `../public`
-pub static PUBLIC_ENDPOINTS: LazyLock<Vec<String>> =
-    LazyLock::new(|| file::get_files_in_directory("./public").unwrap_or_default());
+pub static PUBLIC_ENDPOINTS: Reloadable<Vec<String>> =
+    Reloadable::new(|| file::get_files_in_directory("./public").unwrap_or_default());
 
 /// An HashMap of all the CSS files, with their content being the value
-pub static CSS_FILES: LazyLock<HashMap<FileName, FileContent>> = LazyLock::new(|| {
-    get_files_in_directory_per_extensions("src", &["css"])
-        .into_iter()
-        .map(|file| {
-            (
-                format!("/{file}"),
-                std::fs::read_to_string(file).exit_with_msg_if_err("File should always exist."),
-            )
-        })
-        .collect()
-});
+pub static CSS_FILES: Reloadable<HashMap<FileName, FileContent>> =
+    Reloadable::new(|| read_source_files("css"));
 
 /// An HashMap of all the JavaScript files, with their content being the value
-pub static JS_FILES: LazyLock<HashMap<FileName, FileContent>> = LazyLock::new(|| {
-    get_files_in_directory_per_extensions("src", &["js"])
+pub static JS_FILES: Reloadable<HashMap<FileName, FileContent>> =
+    Reloadable::new(|| read_source_files("js"));
+
+/// The content of the files of `src` with this extension, by URL. The files that can't be read,
+/// like the ones removed while they're listed, are skipped.
+fn read_source_files(extension: &str) -> HashMap<FileName, FileContent> {
+    get_files_in_directory_per_extensions("src", &[extension])
         .into_iter()
-        .map(|file| {
-            (
-                format!("/{file}"),
-                std::fs::read_to_string(file).exit_with_msg_if_err("File should always exist."),
-            )
+        .filter_map(|file| {
+            match std::fs::read_to_string(&file) {
+                Ok(content) => Some((format!("/{file}"), content)),
+                Err(err) => {
+                    log::warn!("Couldn't read `{file}`: {err}");
+                    None
+                },
+            }
         })
         .collect()
-});
+}
 
 pub static ENV_TYPE: LazyLock<EnvType> = LazyLock::new(|| {
     dotenv().exit_with_msg_if_err("Couldn't load environment.");
@@ -59,15 +59,43 @@ pub static PORT: LazyLock<u16> = LazyLock::new(|| {
         .exit_with_msg_if_err("Port is not a valid `u16`")
 });
 
+/// The port on which `/metrics`, `/healthz` and `/readyz` are served.
+///
+/// If `ADMIN_PORT` is not specified in the environment, they are served on [`PORT`].
+pub static ADMIN_PORT: LazyLock<Option<u16>> = LazyLock::new(|| {
+    dotenv().exit_with_msg_if_err("Couldn't load environment.");
+
+    std::env::var("ADMIN_PORT").ok().map(|port| {
+        port.parse::<u16>()
+            .exit_with_msg_if_err("Admin port is not a valid `u16`")
+    })
+});
+
 
+pub mod action;
+pub mod alpine;
+pub mod assets;
+pub mod bundle;
 pub mod cache;
 pub mod components_files;
 pub mod config;
 pub mod dependencies;
 pub mod env;
 pub mod err;
+pub mod error_page;
+pub mod health;
+pub mod htmx;
+pub mod import_map;
+pub mod imports;
+pub mod integrity;
 pub mod layer;
 pub mod layout;
+pub mod linked_file;
+pub mod metrics;
 pub mod packages_files;
+pub mod precompressed;
+pub mod reloadable;
 pub mod response;
+pub mod session;
+pub mod streaming;
 pub mod tsconfig;
@@ -1,63 +1,109 @@
 use {
-    super::{config::TomlLoadingError, err::ExitWithMessageIfErr},
+    super::{
+        config::TomlLoadingError,
+        err::ExitWithMessageIfErr,
+        linked_file::{FileKind, LinkedFile},
+    },
     crate::{concat_paths, shared::wini::config::SERVER_CONFIG},
-    serde::{Deserialize, Deserializer, de::Visitor},
-    std::{collections::HashMap, io, sync::LazyLock},
+    serde::Deserialize,
+    std::{collections::HashMap, io, path::PathBuf, sync::LazyLock},
 };
 
+/// The files of a package in `packages-files.toml`: one or a list of them
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum PackageFiles {
+    One(PackageFile),
+    Many(Vec<PackageFile>),
+}
+
+/// A file of a package: its path in the package, or a table with its path in `file` and how it's
+/// linked, such as `{ file = "dist/print.css", media = "print" }`
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum PackageFile {
+    Path(String),
+    Linked(LinkedPackageFile),
+}
+
+#[derive(Debug, Deserialize)]
+struct LinkedPackageFile {
+    #[serde(flatten)]
+    file: LinkedFile,
+    /// The path in the package of the copy of a file of another website, against which its
+    /// integrity is verified
+    vendored: Option<String>,
+}
+
+/// A file of a package, with the copy of it that has the same content
 #[derive(Debug)]
-pub enum VecOrString {
-    Vec(Vec<String>),
-    String(String),
+pub struct VendoredFile {
+    pub package: String,
+    pub linked_file: LinkedFile,
+    /// The file served for a file of the website, or the `vendored` one for a file of another
+    /// website
+    pub path: Option<PathBuf>,
 }
 
-struct VecOrStringVisitor;
 
-impl<'de> Visitor<'de> for VecOrStringVisitor {
-    type Value = VecOrString;
+/// The files on which a package depend on
+pub static PACKAGES_FILES: LazyLock<HashMap<String, Vec<LinkedFile>>> = LazyLock::new(|| {
+    read_packages_files()
+        .into_iter()
+        .map(|(package, files)| {
+            let files = files
+                .into_iter()
+                .map(|(file, _)| {
+                    let is_verifiable = matches!(
+                        file.kind(),
+                        FileKind::Script | FileKind::Module | FileKind::Stylesheet
+                    );
+                    if !file.is_local() && is_verifiable && file.integrity.is_none() {
+                        log::warn!(
+                            "`{}` of `{package}` is linked without integrity: pin it in \
+                             `packages-files.toml`",
+                            file.url
+                        );
+                    }
 
-    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-        formatter.write_str("a string or a vector of strings")
-    }
+                    file
+                })
+                .collect();
 
-    fn visit_seq<V>(self, mut seq: V) -> Result<Self::Value, V::Error>
-    where
-        V: serde::de::SeqAccess<'de>,
-    {
-        let mut vec = Vec::new();
-        while let Some(value) = seq.next_element::<String>()? {
-            vec.push(value);
-        }
-        Ok(VecOrString::Vec(vec))
-    }
+            (package, files)
+        })
+        .collect()
+});
 
-    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
-    where
-        E: serde::de::Error,
-    {
-        Ok(VecOrString::String(value.to_string()))
-    }
 
-    fn visit_string<E>(self, value: String) -> Result<Self::Value, E>
-    where
-        E: serde::de::Error,
-    {
-        Ok(VecOrString::String(value))
-    }
-}
+/// The files of the packages, with their copy in the project
+pub fn vendored_files() -> Vec<VendoredFile> {
+    let mut packages = read_packages_files().into_iter().collect::<Vec<_>>();
+    packages.sort_by(|(a, _), (b, _)| a.cmp(b));
 
+    packages
+        .into_iter()
+        .flat_map(|(package, files)| {
+            files.into_iter().map(move |(linked_file, vendored)| {
+                let path = if linked_file.is_local() {
+                    Some(PathBuf::from(format!("./public{}", linked_file.url)))
+                } else {
+                    vendored.map(|vendored| concat_paths!("./node_modules", &package, &vendored))
+                };
 
-impl<'de> Deserialize<'de> for VecOrString {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        deserializer.deserialize_any(VecOrStringVisitor)
-    }
+                VendoredFile {
+                    package: package.clone(),
+                    linked_file,
+                    path,
+                }
+            })
+        })
+        .collect()
 }
 
-/// The files on which a package depend on
-pub static PACKAGES_FILES: LazyLock<HashMap<String, VecOrString>> = LazyLock::new(|| {
+
+/// The files of each package in `packages-files.toml`, with the path of their vendored copy
+fn read_packages_files() -> HashMap<String, Vec<(LinkedFile, Option<String>)>> {
     fn module_path_from_short_name(package: &str, file: &str) -> String {
         if file.contains("://") {
             file.to_string()
@@ -87,27 +133,41 @@ pub static PACKAGES_FILES: LazyLock<HashMap<String, VecOrString>> = LazyLock::ne
         })
         .exit_with_msg_if_err("Error while reading file");
 
-    let hashmap: HashMap<String, VecOrString> =
+    let hashmap: HashMap<String, PackageFiles> =
         toml::from_str(&file).exit_with_msg_if_err("Unexpected error while parsing TOML");
 
     hashmap
         .into_iter()
-        .map(|(key, vec_or_string)| {
-            (
-                key.clone(),
-                match vec_or_string {
-                    VecOrString::Vec(v) => {
-                        VecOrString::Vec(
-                            v.into_iter()
-                                .map(|file| module_path_from_short_name(&key, &file))
-                                .collect(),
-                        )
-                    },
-                    VecOrString::String(s) => {
-                        VecOrString::String(module_path_from_short_name(&key, &s))
-                    },
-                },
-            )
+        .map(|(key, files)| {
+            let files = match files {
+                PackageFiles::One(file) => vec![file],
+                PackageFiles::Many(files) => files,
+            };
+
+            let files = files
+                .into_iter()
+                .map(|file| {
+                    match file {
+                        PackageFile::Path(path) => {
+                            (
+                                LinkedFile::new(module_path_from_short_name(&key, &path)),
+                                None,
+                            )
+                        },
+                        PackageFile::Linked(LinkedPackageFile { file, vendored }) => {
+                            (
+                                LinkedFile {
+                                    url: module_path_from_short_name(&key, &file.url),
+                                    ..file
+                                },
+                                vendored,
+                            )
+                        },
+                    }
+                })
+                .collect();
+
+            (key, files)
         })
         .collect()
-});
+}
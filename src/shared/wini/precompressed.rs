@@ -0,0 +1,201 @@
+//! The static files compressed once, when they are loaded, instead of with each response: the CSS
+//! and JavaScript files, and the large public files that can be compressed.
+//!
+//! The responses with a `Content-Encoding` aren't compressed again by the `CompressionLayer`, and
+//! the other static files are marked with [`StaticFile`] so that they aren't either.
+//!
+//! When they are loaded again, only the files whose content changed are compressed again.
+
+use {
+    super::{CSS_FILES, JS_FILES, PUBLIC_ENDPOINTS, assets::Validators, reloadable::Reloadable},
+    axum::body::Bytes,
+    hyper::{HeaderMap, Response, header::ACCEPT_ENCODING},
+    std::{collections::HashMap, io::Write, path::Path},
+    tower_http::compression::Predicate,
+};
+
+
+/// The public files smaller than this aren't worth compressing
+const MIN_PUBLIC_FILE_SIZE: usize = 1024;
+
+/// The extensions of the public files that can be compressed. The other ones, such as images and
+/// fonts, already are.
+const COMPRESSIBLE_EXTENSIONS: [&str; 9] = [
+    "css", "js", "mjs", "json", "map", "svg", "txt", "xml", "html",
+];
+
+/// The compressed versions of the static files, by URL
+pub static PRECOMPRESSED: Reloadable<HashMap<String, PrecompressedFile>> =
+    Reloadable::new(precompress_all);
+
+
+/// The encodings in which the files are compressed, from the preferred one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Brotli,
+    Zstd,
+    Gzip,
+}
+
+impl Encoding {
+    const ALL: [Self; 3] = [Self::Brotli, Self::Zstd, Self::Gzip];
+
+    /// The name of the encoding in `Accept-Encoding` and `Content-Encoding`
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Brotli => "br",
+            Self::Zstd => "zstd",
+            Self::Gzip => "gzip",
+        }
+    }
+
+    fn compress(self, content: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            Self::Brotli => {
+                let mut compressed = Vec::new();
+                brotli::CompressorWriter::new(&mut compressed, 4096, 11, 22).write_all(content)?;
+                Ok(compressed)
+            },
+            Self::Zstd => zstd::encode_all(content, 19),
+            Self::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+                encoder.write_all(content)?;
+                encoder.finish()
+            },
+        }
+    }
+}
+
+
+/// A file compressed in each encoding in which it is smaller
+#[derive(Debug, Clone, Default)]
+pub struct Precompressed(Vec<(Encoding, Bytes)>);
+
+/// A static file compressed when it was loaded
+#[derive(Debug)]
+pub struct PrecompressedFile {
+    pub precompressed: Precompressed,
+    /// The validators of its content, needed for the public files, which aren't in the manifest
+    pub validators: Validators,
+}
+
+impl Precompressed {
+    pub fn new(url: &str, content: &[u8]) -> Self {
+        Self(
+            Encoding::ALL
+                .into_iter()
+                .filter_map(|encoding| {
+                    match encoding.compress(content) {
+                        Ok(compressed) if compressed.len() < content.len() => {
+                            Some((encoding, Bytes::from(compressed)))
+                        },
+                        Ok(_) => None,
+                        Err(err) => {
+                            log::warn!(
+                                "Couldn't compress `{url}` with {}: {err}",
+                                encoding.as_str()
+                            );
+                            None
+                        },
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// The preferred version of the file among the encodings accepted by the client
+    pub fn best(&self, headers: &HeaderMap) -> Option<(Encoding, Bytes)> {
+        let accepted = headers
+            .get_all(ACCEPT_ENCODING)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|encoding| {
+                let mut params = encoding.split(';').map(str::trim);
+                let name = params.next()?.to_ascii_lowercase();
+                let quality = params
+                    .find_map(|param| param.strip_prefix("q="))
+                    .map_or(1., |quality| quality.parse().unwrap_or(0.));
+
+                Some((name, quality))
+            })
+            .collect::<Vec<(String, f32)>>();
+
+        let is_accepted = |encoding: Encoding| {
+            accepted
+                .iter()
+                .find(|(name, _)| name == encoding.as_str())
+                .or_else(|| accepted.iter().find(|(name, _)| name == "*"))
+                .is_some_and(|(_, quality)| *quality > 0.)
+        };
+
+        self.0
+            .iter()
+            .find(|(encoding, _)| is_accepted(*encoding))
+            .map(|(encoding, compressed)| (*encoding, compressed.clone()))
+    }
+}
+
+
+fn precompress_all() -> HashMap<String, PrecompressedFile> {
+    let previous = PRECOMPRESSED.loaded().unwrap_or_default();
+    let mut precompressed = HashMap::new();
+
+    let mut insert = |url: &str, content: &[u8], path: &str| {
+        let validators = Validators::new(content, path);
+        let file = match previous.get(url) {
+            Some(file) if file.validators.etag == validators.etag => file.precompressed.clone(),
+            _ => Precompressed::new(url, content),
+        };
+
+        precompressed.insert(
+            url.to_owned(),
+            PrecompressedFile {
+                precompressed: file,
+                validators,
+            },
+        );
+    };
+
+    for (url, content) in CSS_FILES.load().iter().chain(JS_FILES.load().iter()) {
+        insert(url, content.as_bytes(), url.trim_start_matches('/'));
+    }
+
+    for url in PUBLIC_ENDPOINTS.load().iter() {
+        let is_compressible = Path::new(url)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| COMPRESSIBLE_EXTENSIONS.contains(&ext));
+        if !is_compressible {
+            continue;
+        }
+
+        let path = format!("./public{url}");
+        match std::fs::read(&path) {
+            Ok(content) if content.len() >= MIN_PUBLIC_FILE_SIZE => insert(url, &content, &path),
+            Ok(_) => {},
+            Err(err) => log::warn!("Couldn't compress the public file `{url}`: {err}"),
+        }
+    }
+
+    precompressed
+}
+
+
+/// Marks the responses of the static files, that aren't compressed by the `CompressionLayer`
+#[derive(Debug, Clone, Copy)]
+pub struct StaticFile;
+
+/// Only compress the responses that aren't [`StaticFile`]s
+#[derive(Debug, Clone, Copy)]
+pub struct NotForStaticFiles;
+
+impl Predicate for NotForStaticFiles {
+    fn should_compress<B>(&self, response: &Response<B>) -> bool
+    where
+        B: axum::body::HttpBody,
+    {
+        response.extensions().get::<StaticFile>().is_none()
+    }
+}
@@ -0,0 +1,54 @@
+use std::sync::{Arc, PoisonError, RwLock};
+
+/// A value computed the first time it's used, like a `LazyLock`, but that can be computed again.
+///
+/// Used for the data read from files, so that it can be reloaded when they change.
+///
+/// # Example
+///
+/// ```ignore
+/// static FILES: Reloadable<Vec<String>> = Reloadable::new(|| read_files());
+///
+/// let files = FILES.load();
+/// FILES.reload();
+/// ```
+pub struct Reloadable<T> {
+    init: fn() -> T,
+    value: RwLock<Option<Arc<T>>>,
+}
+
+impl<T> Reloadable<T> {
+    pub const fn new(init: fn() -> T) -> Self {
+        Self {
+            init,
+            value: RwLock::new(None),
+        }
+    }
+
+    /// The current value, computed if it hasn't been yet
+    pub fn load(&self) -> Arc<T> {
+        if let Some(value) = &*self.value.read().unwrap_or_else(PoisonError::into_inner) {
+            return Arc::clone(value);
+        }
+
+        let mut value = self.value.write().unwrap_or_else(PoisonError::into_inner);
+        Arc::clone(value.get_or_insert_with(|| Arc::new((self.init)())))
+    }
+
+    /// The current value, without computing it: `None` if it hasn't been computed yet, or while it
+    /// is for the first time
+    pub fn loaded(&self) -> Option<Arc<T>> {
+        self.value.try_read().ok().and_then(|value| value.clone())
+    }
+
+    /// Compute the value again. What already loaded the previous value keeps it.
+    pub fn reload(&self) {
+        let new_value = Arc::new((self.init)());
+        *self.value.write().unwrap_or_else(PoisonError::into_inner) = Some(new_value);
+    }
+
+    /// Compute the value if it hasn't been yet, like `LazyLock::force`
+    pub fn force(this: &Self) {
+        this.load();
+    }
+}
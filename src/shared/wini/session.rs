@@ -0,0 +1,482 @@
+//! Sessions stored in signed, and optionally encrypted, cookies.
+//!
+//! The cookie either contains the data of the session itself (`cookie` store), or the ID of a
+//! session kept in a [`SessionStore`] (`memory` and `file` stores, or a custom one set with
+//! [`set_store`]).
+
+use {
+    super::{
+        ENV_TYPE,
+        config::{SERVER_CONFIG, SessionStoreKind},
+        env::EnvType,
+        err::{ExitWithMessageIfErr, ServerError},
+    },
+    aes_gcm::{Aes256Gcm, KeyInit, aead::Aead},
+    axum::{extract::FromRequestParts, http::request::Parts},
+    base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD},
+    hmac::{Hmac, Mac},
+    hyper::StatusCode,
+    serde::{Serialize, de::DeserializeOwned},
+    sha2::{Digest, Sha256},
+    std::{
+        collections::HashMap,
+        path::PathBuf,
+        sync::{Arc, LazyLock, Mutex, OnceLock, PoisonError},
+        time::{SystemTime, UNIX_EPOCH},
+    },
+};
+
+
+/// The key of the CSRF token in the data of the session
+pub const CSRF_TOKEN_KEY: &str = "_csrf";
+
+/// The maximum size of a cookie accepted by browsers
+const MAX_COOKIE_SIZE: usize = 4096;
+
+/// The data of a session
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct SessionData {
+    pub values: HashMap<String, serde_json::Value>,
+    /// Unix timestamp, in seconds
+    pub expires_at: u64,
+}
+
+impl SessionData {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= now()
+    }
+}
+
+
+/// Somewhere to keep the data of the sessions, by their ID.
+///
+/// The methods are called from a blocking thread.
+pub trait SessionStore: Send + Sync {
+    fn load(&self, id: &str) -> Option<SessionData>;
+    fn save(&self, id: &str, data: &SessionData);
+    fn remove(&self, id: &str);
+}
+
+/// Keep the sessions in the memory of the server
+#[derive(Default)]
+pub struct MemoryStore(Mutex<HashMap<String, SessionData>>);
+
+impl SessionStore for MemoryStore {
+    fn load(&self, id: &str) -> Option<SessionData> {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(id)
+            .cloned()
+    }
+
+    fn save(&self, id: &str, data: &SessionData) {
+        let mut sessions = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        sessions.retain(|_, data| !data.is_expired());
+        sessions.insert(id.to_owned(), data.clone());
+    }
+
+    fn remove(&self, id: &str) {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(id);
+    }
+}
+
+/// Keep the sessions in a directory, with one JSON file per session
+pub struct FileStore(PathBuf);
+
+impl FileStore {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir).exit_with_msg_to_compute_if_err(|| {
+            format!(
+                "Couldn't create the directory of the sessions `{}`",
+                dir.display()
+            )
+        });
+        Self(dir)
+    }
+
+    fn file_of(&self, id: &str) -> Option<PathBuf> {
+        // IDs are in base64url, but they shouldn't be able to escape the directory in any case
+        id.bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_')
+            .then(|| self.0.join(format!("{id}.json")))
+    }
+}
+
+impl SessionStore for FileStore {
+    fn load(&self, id: &str) -> Option<SessionData> {
+        let content = std::fs::read(self.file_of(id)?).ok()?;
+        serde_json::from_slice(&content).ok()
+    }
+
+    fn save(&self, id: &str, data: &SessionData) {
+        let Some(file) = self.file_of(id) else {
+            return;
+        };
+        let result = serde_json::to_vec(data)
+            .map_err(|err| err.to_string())
+            .and_then(|content| std::fs::write(file, content).map_err(|err| err.to_string()));
+
+        if let Err(err) = result {
+            log::error!("Couldn't save the session: {err}");
+        }
+    }
+
+    fn remove(&self, id: &str) {
+        if let Some(file) = self.file_of(id) {
+            let _ = std::fs::remove_file(file);
+        }
+    }
+}
+
+
+static STORE: OnceLock<Option<Box<dyn SessionStore>>> = OnceLock::new();
+
+/// Use a custom store for the sessions, instead of the one specified in `./wini.toml`.
+///
+/// This should be called in `main`, before [`init`].
+pub fn set_store<S: SessionStore + 'static>(store: S) {
+    if STORE.set(Some(Box::new(store))).is_err() {
+        log::warn!("The store of the sessions is already set.");
+    }
+}
+
+/// The store of the sessions, or `None` if the data is kept in the cookie
+fn store() -> Option<&'static dyn SessionStore> {
+    STORE
+        .get_or_init(|| {
+            match SERVER_CONFIG.session.store {
+                SessionStoreKind::Cookie => None,
+                SessionStoreKind::Memory => Some(Box::new(MemoryStore::default())),
+                SessionStoreKind::File => {
+                    Some(Box::new(FileStore::new(&SERVER_CONFIG.session.path)))
+                },
+            }
+        })
+        .as_deref()
+}
+
+
+/// The secret used to sign and encrypt the cookies, from `SESSION_SECRET`.
+///
+/// In a local environment, a random secret is used if none is specified.
+static SESSION_SECRET: LazyLock<Vec<u8>> = LazyLock::new(|| {
+    match std::env::var("SESSION_SECRET") {
+        Ok(secret) if secret.len() >= 32 => secret.into_bytes(),
+        Ok(_) => {
+            log::error!("`SESSION_SECRET` should be at least 32 bytes long.");
+            std::process::exit(1);
+        },
+        Err(_) if *ENV_TYPE == EnvType::Local => {
+            log::warn!(
+                "`SESSION_SECRET` not specified in the environment: the sessions won't survive a \
+                 restart."
+            );
+            rand::random::<[u8; 32]>().to_vec()
+        },
+        Err(err) => {
+            log::error!("Couldn't load environment variable `SESSION_SECRET`: {err}");
+            std::process::exit(1);
+        },
+    }
+});
+
+/// Load the secret and the store of the sessions, so that the server exits at startup, rather than
+/// on the first request, if they can't be.
+pub fn init() {
+    LazyLock::force(&SESSION_SECRET);
+    store();
+}
+
+/// Derive a key for a specific usage from the secret
+fn key_for(usage: &str) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(usage)
+        .chain_update(&*SESSION_SECRET)
+        .finalize()
+        .into()
+}
+
+static SIGNING_KEY: LazyLock<[u8; 32]> = LazyLock::new(|| key_for("wini-session-signing"));
+static ENCRYPTION_KEY: LazyLock<[u8; 32]> = LazyLock::new(|| key_for("wini-session-encryption"));
+
+fn mac() -> Hmac<Sha256> {
+    <Hmac<Sha256> as Mac>::new_from_slice(&*SIGNING_KEY).expect("HMAC accepts keys of any size")
+}
+
+/// Sign, and encrypt if specified, the content of a cookie
+fn seal(content: &[u8]) -> String {
+    let payload = if SERVER_CONFIG.session.encrypt {
+        let nonce = rand::random::<[u8; 12]>();
+        let cipher = Aes256Gcm::new(&(*ENCRYPTION_KEY).into());
+        let encrypted = cipher
+            .encrypt(&nonce.into(), content)
+            .expect("Encryption with AES-GCM doesn't fail for inputs of this size");
+        URL_SAFE_NO_PAD.encode([nonce.as_slice(), &encrypted].concat())
+    } else {
+        URL_SAFE_NO_PAD.encode(content)
+    };
+
+    let signature = URL_SAFE_NO_PAD.encode(mac().chain_update(&payload).finalize().into_bytes());
+
+    format!("{payload}.{signature}")
+}
+
+/// Verify the signature of a cookie and decrypt its content
+fn unseal(cookie: &str) -> Option<Vec<u8>> {
+    let (payload, signature) = cookie.split_once('.')?;
+    mac()
+        .chain_update(payload)
+        .verify_slice(&URL_SAFE_NO_PAD.decode(signature).ok()?)
+        .ok()?;
+
+    let content = URL_SAFE_NO_PAD.decode(payload).ok()?;
+    if SERVER_CONFIG.session.encrypt {
+        let (nonce, encrypted) = content.split_at_checked(12)?;
+        let cipher = Aes256Gcm::new(&(*ENCRYPTION_KEY).into());
+        cipher.decrypt(nonce.into(), encrypted).ok()
+    } else {
+        Some(content)
+    }
+}
+
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+/// A new random token, in base64url
+pub fn random_token() -> String {
+    URL_SAFE_NO_PAD.encode(rand::random::<[u8; 32]>())
+}
+
+
+#[derive(Debug)]
+struct SessionState {
+    /// The ID of the session in the store
+    id: Option<String>,
+    /// An ID that isn't used anymore, and that should be removed from the store
+    previous_id: Option<String>,
+    data: SessionData,
+    is_modified: bool,
+    is_destroyed: bool,
+}
+
+/// The session of the current request.
+///
+/// Usable as an argument of `#[page]`s and `#[layout]`s, as long as the session layer is used.
+/// Modifications are saved once the response is computed.
+///
+/// # Example
+///
+/// ```ignore
+/// #[page]
+/// pub async fn render(session: Session) -> Markup {
+///     let visits = session.get::<u32>("visits").unwrap_or_default() + 1;
+///     session.insert("visits", visits);
+///
+///     html! { "You visited this page " (visits) " times" }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Session(Arc<Mutex<SessionState>>);
+
+impl Session {
+    fn state(&self) -> std::sync::MutexGuard<'_, SessionState> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let value = self.state().data.values.get(key).cloned()?;
+        serde_json::from_value(value).ok()
+    }
+
+    pub fn insert<T: Serialize>(&self, key: &str, value: T) {
+        match serde_json::to_value(value) {
+            Ok(value) => {
+                let mut state = self.state();
+                state.data.values.insert(key.to_owned(), value);
+                state.is_modified = true;
+                state.is_destroyed = false;
+            },
+            Err(err) => {
+                log::error!("Couldn't serialize the value of `{key}` in the session: {err}");
+            },
+        }
+    }
+
+    pub fn remove(&self, key: &str) {
+        let mut state = self.state();
+        if state.data.values.remove(key).is_some() {
+            state.is_modified = true;
+        }
+    }
+
+    /// Remove all the data of the session, and its cookie
+    pub fn destroy(&self) {
+        let mut state = self.state();
+        state.data.values.clear();
+        state.previous_id = state.id.take();
+        state.is_destroyed = true;
+    }
+
+    /// Change the ID of the session while keeping its data.
+    ///
+    /// This should be done when the privileges of the user change (e.g. on login), so that an ID
+    /// known before can't be used to act as the user.
+    pub fn regenerate(&self) {
+        let mut state = self.state();
+        state.previous_id = state.id.take();
+        state.data.values.remove(CSRF_TOKEN_KEY);
+        state.is_modified = true;
+        state.is_destroyed = false;
+    }
+
+    /// The CSRF token of the session, generated if there isn't one yet.
+    ///
+    /// Send it back in the `_csrf` field of a form, or in the `X-CSRF-Token` header.
+    pub fn csrf_token(&self) -> String {
+        if let Some(token) = self.get::<String>(CSRF_TOKEN_KEY) {
+            return token;
+        }
+
+        let token = random_token();
+        self.insert(CSRF_TOKEN_KEY, &token);
+        token
+    }
+
+    /// The CSRF token of the session, without generating it
+    pub fn existing_csrf_token(&self) -> Option<String> {
+        self.get(CSRF_TOKEN_KEY)
+    }
+
+    /// Load the session associated to a cookie. An invalid or expired cookie gives a new session.
+    pub async fn load(cookie: Option<&str>) -> Self {
+        let content = cookie.and_then(unseal);
+
+        let (id, data) = match (store(), content) {
+            (Some(store), Some(id)) => {
+                let id = String::from_utf8(id).ok();
+                let data = match id.clone() {
+                    Some(id) => {
+                        tokio::task::spawn_blocking(move || store.load(&id))
+                            .await
+                            .ok()
+                            .flatten()
+                    },
+                    None => None,
+                };
+                (id, data)
+            },
+            (None, Some(content)) => (None, serde_json::from_slice(&content).ok()),
+            (_, None) => (None, None),
+        };
+
+        let (id, data) = match data {
+            Some(data) if !data.is_expired() => (id, data),
+            _ => (None, SessionData::default()),
+        };
+
+        Self(Arc::new(Mutex::new(SessionState {
+            id,
+            previous_id: None,
+            data,
+            is_modified: false,
+            is_destroyed: false,
+        })))
+    }
+
+    /// Save the session if it has been modified, and return the `Set-Cookie` header to send
+    pub async fn save(&self) -> Option<String> {
+        let config = &SERVER_CONFIG.session;
+        let store = store();
+
+        let (id, previous_id, data, is_destroyed) = {
+            let mut state = self.state();
+            if !state.is_modified && !state.is_destroyed {
+                return None;
+            }
+
+            state.data.expires_at = now() + config.max_age;
+            if store.is_some() && state.id.is_none() && !state.is_destroyed {
+                state.id = Some(random_token());
+            }
+            state.is_modified = false;
+
+            (
+                state.id.clone(),
+                state.previous_id.take(),
+                state.data.clone(),
+                state.is_destroyed,
+            )
+        };
+
+        let cookie_content = match store {
+            Some(store) => {
+                let id_to_save = id.clone();
+                let _ = tokio::task::spawn_blocking(move || {
+                    if let Some(previous_id) = previous_id {
+                        store.remove(&previous_id);
+                    }
+                    if let Some(id) = id_to_save {
+                        store.save(&id, &data);
+                    }
+                })
+                .await;
+
+                id.map(String::into_bytes)
+            },
+            None if is_destroyed => None,
+            None => serde_json::to_vec(&data).ok(),
+        };
+
+        let secure = if *ENV_TYPE == EnvType::Local {
+            ""
+        } else {
+            "; Secure"
+        };
+
+        let cookie = match cookie_content {
+            Some(content) if !is_destroyed => {
+                let cookie = format!(
+                    "{}={}; Max-Age={}; Path=/; HttpOnly; SameSite=Lax{secure}",
+                    config.cookie_name,
+                    seal(&content),
+                    config.max_age
+                );
+                if cookie.len() > MAX_COOKIE_SIZE {
+                    log::warn!(
+                        "The cookie of the session is bigger than {MAX_COOKIE_SIZE} bytes, and \
+                         will likely be rejected by the browser. Consider using another store."
+                    );
+                }
+                cookie
+            },
+            _ => {
+                format!(
+                    "{}=; Max-Age=0; Path=/; HttpOnly; SameSite=Lax{secure}",
+                    config.cookie_name
+                )
+            },
+        };
+
+        Some(cookie)
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Session {
+    type Rejection = ServerError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts.extensions.get::<Self>().cloned().ok_or_else(|| {
+            log::error!("`Session` is used, but the session layer isn't.");
+            StatusCode::INTERNAL_SERVER_ERROR.into()
+        })
+    }
+}
@@ -0,0 +1,219 @@
+//! Streaming of the pages: the slow components can be deferred, so that the rest of the page, with
+//! its `<head>`, is sent without waiting for them.
+//!
+//! A deferred component is first rendered as an empty placeholder. Its content is streamed later
+//! in the same response, once it's ready, with a script that moves it into its placeholder.
+//!
+//! The streamed responses aren't compressed, since the compression would hold the chunks back until
+//! the end of the response.
+
+use {
+    super::{err::ServerError, linked_file::LinkedFile},
+    crate::utils::wini::panic,
+    futures_util::{Stream, stream},
+    hyper::Response,
+    maud::{Markup, PreEscaped, html},
+    std::{
+        collections::HashSet,
+        sync::{Arc, Mutex, PoisonError},
+    },
+    tokio::task::{JoinError, JoinSet},
+    tower_http::compression::Predicate,
+};
+
+
+tokio::task_local! {
+    /// The deferred components of the response being rendered
+    static DEFERRED: Deferred;
+}
+
+/// The components deferred while rendering a response
+#[derive(Clone, Default)]
+pub struct Deferred(Arc<Mutex<DeferredComponents>>);
+
+#[derive(Default)]
+struct DeferredComponents {
+    next_id: usize,
+    /// The components being rendered, with the ID of their placeholder
+    tasks: JoinSet<(usize, Markup)>,
+}
+
+
+/// Render `content` after the rest of the page: an empty placeholder is returned right away, and
+/// `content` is streamed once it's ready. If it panics, the error is rendered in its place.
+///
+/// Outside of the rendering of a response, such as in another deferred component, `content` is
+/// rendered right away.
+///
+/// This is used by `#[component(defer)]`.
+pub async fn defer<F>(content: F) -> Markup
+where
+    F: Future<Output = Markup> + Send + 'static,
+{
+    let Ok(deferred) = DEFERRED.try_with(Clone::clone) else {
+        return content.await;
+    };
+
+    let id = {
+        let mut components = deferred.components();
+        let id = components.next_id;
+        components.next_id += 1;
+        components.tasks.spawn(async move {
+            let content = panic::catch(content)
+                .await
+                .unwrap_or_else(|kind| ServerError::from(kind).render_inline());
+            (id, content)
+        });
+        id
+    };
+
+    placeholder(id, None)
+}
+
+
+impl Deferred {
+    /// Run `future`, which renders a response, and collect the components it deferred
+    pub async fn scope<F: Future>(future: F) -> (F::Output, Self) {
+        let deferred = Self::default();
+        let output = DEFERRED.scope(deferred.clone(), future).await;
+        (output, deferred)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.components().tasks.is_empty()
+    }
+
+    /// Wait for all the deferred components and put their content into their placeholders in
+    /// `html`. Used for the responses that aren't streamed, such as the answers to htmx.
+    pub async fn resolve(self, mut html: String) -> String {
+        let mut tasks = self.take_tasks();
+
+        while let Some(result) = tasks.join_next().await {
+            if let Some((id, content)) = joined(result) {
+                html = html.replacen(
+                    &placeholder(id, None).into_string(),
+                    &placeholder(id, Some(&content)).into_string(),
+                    1,
+                );
+            }
+        }
+
+        html
+    }
+
+    /// The content of the deferred components, in the order in which they're ready, each one
+    /// followed by the script that moves it into its placeholder.
+    ///
+    /// The files linked to the components are added to the chunks, except the ones that are in
+    /// `linked_files`: the URLs of the files already linked in the document.
+    pub fn stream(
+        self,
+        nonce: Option<String>,
+        linked_files: HashSet<String>,
+    ) -> impl Stream<Item = String> + Send {
+        stream::unfold(
+            (self.take_tasks(), linked_files),
+            move |(mut tasks, mut linked_files)| {
+                let nonce = nonce.clone();
+
+                async move {
+                    loop {
+                        if let Some((id, content)) = joined(tasks.join_next().await?) {
+                            let chunk = chunk(id, content, nonce.as_deref(), &mut linked_files);
+                            return Some((chunk, (tasks, linked_files)));
+                        }
+                    }
+                }
+            },
+        )
+    }
+
+    fn components(&self) -> std::sync::MutexGuard<'_, DeferredComponents> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn take_tasks(&self) -> JoinSet<(usize, Markup)> {
+        std::mem::take(&mut self.components().tasks)
+    }
+}
+
+
+/// The component, unless its task couldn't be completed
+fn joined(result: Result<(usize, Markup), JoinError>) -> Option<(usize, Markup)> {
+    match result {
+        Ok(component) => Some(component),
+        Err(err) => {
+            log::error!("A deferred component couldn't be rendered: {err}");
+            None
+        },
+    }
+}
+
+/// Marks the responses streamed with their deferred components
+#[derive(Debug, Clone, Copy)]
+pub struct Streamed;
+
+/// Only compress the responses that aren't [`Streamed`]
+#[derive(Debug, Clone, Copy)]
+pub struct NotForStreamedPages;
+
+impl Predicate for NotForStreamedPages {
+    fn should_compress<B>(&self, response: &Response<B>) -> bool
+    where
+        B: axum::body::HttpBody,
+    {
+        response.extensions().get::<Streamed>().is_none()
+    }
+}
+
+
+fn placeholder_id(id: usize) -> String {
+    format!("wini-deferred-{id}")
+}
+
+/// The element in which a deferred component is rendered. It doesn't affect the layout of the
+/// page.
+fn placeholder(id: usize, content: Option<&Markup>) -> Markup {
+    html! {
+        wini-deferred id=(placeholder_id(id)) style="display: contents" {
+            @if let Some(content) = content {
+                (content)
+            }
+        }
+    }
+}
+
+/// The content of a deferred component, in a `<template>` moved into its placeholder
+fn chunk(
+    id: usize,
+    content: Markup,
+    nonce: Option<&str>,
+    linked_files: &mut HashSet<String>,
+) -> String {
+    let mut files = content
+        .linked_files
+        .iter()
+        .map(|file| LinkedFile::decode(file))
+        .filter(|file| linked_files.insert(file.url.clone()))
+        .collect::<Vec<_>>();
+    // They're in a `HashSet`, so that they would be in another order each time
+    files.sort();
+
+    let placeholder_id = placeholder_id(id);
+    let template_id = format!("{placeholder_id}-content");
+
+    html! {
+        template id=(template_id) {
+            @for file in files {
+                (file.to_html(nonce))
+            }
+            (content)
+        }
+        script nonce=[nonce] {
+            (PreEscaped(format!(
+                r#"document.getElementById("{placeholder_id}").replaceChildren(document.getElementById("{template_id}").content);document.getElementById("{template_id}").remove();document.currentScript.remove();"#
+            )))
+        }
+    }
+    .into_string()
+}
@@ -1,68 +1,325 @@
 //! Handle topics linked to `tsconfig.json`
+//!
+//! It's read like `tsc` does: with its comments and trailing commas, after the files it `extends`,
+//! whose options it overrides. The `paths` are relative to `baseUrl`, or else to the file that sets
+//! them, and the imports that aren't relative are resolved like with `tsc`:
+//! - with the pattern of `paths` that matches them exactly, or else the one with the longest prefix
+//!   before its `*`, trying its targets in their order
+//! - then from `baseUrl`
+//! - then as a package
 
 use {
+    super::dependencies::normalize_relative_path,
     serde::Deserialize,
-    std::{collections::HashMap, sync::LazyLock},
+    std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+        sync::LazyLock,
+    },
 };
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
 struct CompilerOptions {
+    base_url: Option<String>,
     paths: Option<HashMap<String, Vec<String>>>,
 }
 
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
 pub struct TsConfig {
+    extends: Option<Extends>,
     compiler_options: CompilerOptions,
 }
 
+/// The files extended by a `tsconfig.json`, whose options are overridden by the next ones
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum Extends {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// The options of a `tsconfig.json` merged with the ones of the files it extends, with their paths
+/// relative to the root of the project
+#[derive(Debug, Default)]
+struct MergedOptions {
+    base_url: Option<PathBuf>,
+    /// The `paths`, with the directory of the file that sets them
+    paths: Option<(HashMap<String, Vec<String>>, PathBuf)>,
+}
+
 
-/// Get all the abbreviations of a path from `./tsconfig.json`.
+/// The `baseUrl` and `paths` of `./tsconfig.json`.
 ///
-/// If tsconfig.json doesn't exist - for some reason - an empty HashMap will be returned.
-pub static TSCONFIG_PATHS: LazyLock<HashMap<String, Vec<String>>> = LazyLock::new(|| {
-    let tsconfig = match std::fs::read_to_string("./tsconfig.json") {
-        Ok(s) => s,
-        Err(why) => {
-            log::warn!("{why:#?}");
-            return HashMap::new();
-        },
-    };
+/// If tsconfig.json doesn't exist - for some reason - it's empty.
+pub static TSCONFIG_PATHS: LazyLock<TsConfigPaths> =
+    LazyLock::new(|| TsConfigPaths::load(Path::new("./tsconfig.json")));
+
+
+#[derive(Debug, Default)]
+pub struct TsConfigPaths {
+    /// The directory from which the imports that aren't relative are resolved
+    base_url: Option<PathBuf>,
+    /// The patterns of `paths`, from the one that applies first to the one that applies last
+    patterns: Vec<PathPattern>,
+}
+
+/// A pattern of `paths`, such as `"~/utils/*": ["./src/utils/*"]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathPattern {
+    /// With at most one `*`, or none to match only this import
+    pub key: String,
+    /// The paths to which it's mapped from the root of the project, in the order in which they
+    /// are tried. Their `*` is replaced by what the one of the key matched.
+    pub targets: Vec<PathBuf>,
+}
 
-    let config: TsConfig = serde_json::from_str(&tsconfig).expect("Failed to parse JSON");
+impl TsConfigPaths {
+    /// Read a `tsconfig.json` and the files it extends
+    pub fn load(path: &Path) -> Self {
+        if !path.is_file() {
+            log::warn!("Couldn't find `{}`", path.display());
+            return Self::default();
+        }
 
-    let paths = config.compiler_options.paths.unwrap_or_default();
+        match load_options(path, &mut Vec::new()) {
+            Ok(options) => Self::from_options(options),
+            Err(err) => {
+                log::error!("{err}");
+                Self::default()
+            },
+        }
+    }
+
+    fn from_options(options: MergedOptions) -> Self {
+        let mut patterns = options
+            .paths
+            .map(|(paths, directory)| {
+                // `baseUrl` wins over the directory of the file that sets the paths
+                let directory = options.base_url.clone().unwrap_or(directory);
+
+                paths
+                    .into_iter()
+                    .filter(|(key, _)| {
+                        let is_valid = key.matches('*').count() <= 1;
+                        if !is_valid {
+                            log::warn!("`{key}` of `tsconfig.json` can only have one `*`");
+                        }
+                        is_valid
+                    })
+                    .map(|(key, targets)| {
+                        PathPattern {
+                            key,
+                            targets: targets
+                                .iter()
+                                .map(|target| normalize_relative_path(directory.join(target)))
+                                .collect(),
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        // The exact matches first, and then the longest prefixes, like `tsc`
+        patterns.sort_by(|a, b| {
+            a.prefix()
+                .is_some()
+                .cmp(&b.prefix().is_some())
+                .then_with(|| b.prefix().map(str::len).cmp(&a.prefix().map(str::len)))
+                .then_with(|| a.key.cmp(&b.key))
+        });
 
-    paths
-        .into_iter()
-        .map(|(k, v)| {
-            let key = k.strip_suffix("/*").unwrap_or(&k).to_string();
-            let values = v
-                .into_iter()
-                .map(|path| path.strip_suffix("/*").unwrap_or(&path).to_string())
-                .collect();
-            (key, values)
+        Self {
+            base_url: options.base_url,
+            patterns,
+        }
+    }
+
+    /// The patterns of `paths`, from the one that applies first to the one that applies last
+    pub fn patterns(&self) -> &[PathPattern] {
+        &self.patterns
+    }
+
+    pub fn base_url(&self) -> Option<&Path> {
+        self.base_url.as_deref()
+    }
+
+    /// The paths to which `paths` maps an import, in the order in which they are tried, or `None`
+    /// if no pattern matches it
+    pub fn mapped(&self, import: &str) -> Option<Vec<PathBuf>> {
+        self.patterns.iter().find_map(|pattern| {
+            let matched = pattern.matches(import)?;
+            Some(
+                pattern
+                    .targets
+                    .iter()
+                    .map(|target| PathBuf::from(target.to_string_lossy().replacen('*', matched, 1)))
+                    .collect(),
+            )
         })
-        .collect()
-});
+    }
+
+    /// The path of an import from `baseUrl`, if it's set
+    pub fn from_base_url(&self, import: &str) -> Option<PathBuf> {
+        self.base_url
+            .as_ref()
+            .map(|base_url| normalize_relative_path(base_url.join(import)))
+    }
+}
+
+impl PathPattern {
+    /// What is before the `*` of the key, or `None` if it matches only one import
+    pub fn prefix(&self) -> Option<&str> {
+        self.key.split_once('*').map(|(prefix, _)| prefix)
+    }
+
+    /// What the `*` matches in an import, or an empty string if it's an exact match
+    pub fn matches<'i>(&self, import: &'i str) -> Option<&'i str> {
+        match self.key.split_once('*') {
+            Some((prefix, suffix)) => {
+                import
+                    .strip_prefix(prefix)?
+                    .strip_suffix(suffix)
+                    // The prefix and the suffix can't overlap
+                    .filter(|_| import.len() >= prefix.len() + suffix.len())
+            },
+            None => (import == self.key).then_some(""),
+        }
+    }
+}
+
+
+/// The options of a `tsconfig.json`, after the ones of the files it extends
+fn load_options(path: &Path, extended_by: &mut Vec<PathBuf>) -> Result<MergedOptions, String> {
+    let normalized_path = normalize_relative_path(path);
+    if extended_by.contains(&normalized_path) {
+        return Err(format!(
+            "`{}` extends itself through `{}`",
+            path.display(),
+            extended_by
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join(" -> ")
+        ));
+    }
+
+    let jsonc = std::fs::read_to_string(path)
+        .map_err(|err| format!("Couldn't read `{}`: {err}", path.display()))?;
+    let config: TsConfig = serde_json::from_str(&strip_jsonc(&jsonc))
+        .map_err(|err| format!("Couldn't parse `{}`: {err}", path.display()))?;
+    let directory = path.parent().unwrap_or_else(|| Path::new("")).to_path_buf();
+
+    let extends = match config.extends {
+        Some(Extends::One(extended)) => vec![extended],
+        Some(Extends::Many(extended)) => extended,
+        None => Vec::new(),
+    };
+
+    let mut options = MergedOptions::default();
 
-pub trait TsConfigPathsPrefix {
-    /// Get all the prefixes that should be resolved in a `tsconfig.json` file
-    fn prefixes(&self) -> Vec<&str>;
+    extended_by.push(normalized_path);
+    for extended in extends {
+        let extended_options = load_options(&extended_path(&directory, &extended), extended_by)?;
+        options = MergedOptions {
+            base_url: extended_options.base_url.or(options.base_url),
+            paths: extended_options.paths.or(options.paths),
+        };
+    }
+    extended_by.pop();
+
+    if let Some(base_url) = config.compiler_options.base_url {
+        options.base_url = Some(normalize_relative_path(directory.join(base_url)));
+    }
+    if let Some(paths) = config.compiler_options.paths {
+        options.paths = Some((paths, directory));
+    }
+
+    Ok(options)
 }
 
-impl TsConfigPathsPrefix for HashMap<String, Vec<String>> {
-    fn prefixes(&self) -> Vec<&str> {
-        let mut prefixes = self
-            .keys()
-            .map(std::string::String::as_str)
-            .collect::<Vec<&str>>();
-        // We sort them from the longest to shortest, because we need to first get the complete
-        // path.
-        // In case of:
-        // ["~/utils/*", "~/*"]
-        // utils needs to be resolved first
-        prefixes.sort_by_key(|k| std::cmp::Reverse(k.len()));
-        prefixes
+/// The path of a file extended by a `tsconfig.json` in `directory`: a relative path, with or
+/// without its `.json` extension, or a package such as `@tsconfig/strictest`
+fn extended_path(directory: &Path, extended: &str) -> PathBuf {
+    let path = if extended.starts_with('.') || Path::new(extended).is_absolute() {
+        directory.join(extended)
+    } else {
+        directory.join("node_modules").join(extended)
+    };
+
+    // `.base` of `tsconfig.base` isn't an extension, so `.json` is appended rather than set
+    let with_json = PathBuf::from(format!("{}.json", path.display()));
+
+    if path.is_file() {
+        path
+    } else if !extended.ends_with(".json") && with_json.is_file() {
+        with_json
+    } else {
+        path.join("tsconfig.json")
+    }
+}
+
+/// The JSON of a JSONC file such as `tsconfig.json`: without its comments and trailing commas. The
+/// lines stay the same, for the errors.
+pub fn strip_jsonc(jsonc: &str) -> String {
+    let mut json = String::with_capacity(jsonc.len());
+    let mut chars = jsonc.chars().peekable();
+    // The index of the last comma, if it might be trailing
+    let mut last_comma = None;
+
+    while let Some(char) = chars.next() {
+        match char {
+            '"' => {
+                last_comma = None;
+                json.push(char);
+                while let Some(char) = chars.next() {
+                    json.push(char);
+                    match char {
+                        '\\' => json.extend(chars.next()),
+                        '"' => break,
+                        _ => {},
+                    }
+                }
+            },
+            '/' if chars.peek() == Some(&'/') => {
+                for char in chars.by_ref() {
+                    if char == '\n' {
+                        json.push(char);
+                        break;
+                    }
+                }
+            },
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for char in chars.by_ref() {
+                    if previous == '*' && char == '/' {
+                        break;
+                    }
+                    if char == '\n' {
+                        json.push(char);
+                    }
+                    previous = char;
+                }
+            },
+            ',' => {
+                last_comma = Some(json.len());
+                json.push(char);
+            },
+            '}' | ']' => {
+                if let Some(index) = last_comma.take() {
+                    json.replace_range(index..=index, " ");
+                }
+                json.push(char);
+            },
+            char if char.is_whitespace() => json.push(char),
+            char => {
+                last_comma = None;
+                json.push(char);
+            },
+        }
     }
+
+    json
 }
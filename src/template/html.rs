@@ -1,12 +1,54 @@
-use maud::{Markup, PreEscaped, DOCTYPE};
+use {
+    crate::{
+        shared::wini::{
+            ENV_TYPE,
+            assets::MANIFEST,
+            config::SERVER_CONFIG,
+            env::EnvType,
+            import_map::{IMPORT_MAP, ImportMap},
+            linked_file::{FileKind, LinkedFile, Placement},
+        },
+        utils::wini::live_reload,
+    },
+    maud::{DOCTYPE, Markup, PreEscaped},
+};
 
+/// Where the content of the page goes in the document
+const BODY_MARKER: &str = "<!--wini-body-->";
+
+/// The document around the content of a page: what comes before it, with the `<head>`, and what
+/// comes after it. They are sent separately, so that the `<head>` is sent without waiting for the
+/// deferred components.
+///
+/// The files are linked in their order, the scripts after the other ones. When the scripts are ES
+/// modules, the modules they import are preloaded, from `module_preloads`.
 pub fn html(
-    s: &str,
-    scripts_files: Vec<String>,
-    style_sheets: Vec<String>,
+    files: Vec<LinkedFile>,
+    module_preloads: Vec<String>,
     meta: &Markup,
-) -> String {
-    maud::html! {
+    nonce: Option<&str>,
+) -> (String, String) {
+    let import_map = SERVER_CONFIG.scripts.modules.then(|| IMPORT_MAP.load());
+    document(files, module_preloads, meta, nonce, import_map.as_deref())
+}
+
+/// The document of [`html`], with the import map of the modules if the scripts are ES modules
+pub fn document(
+    files: Vec<LinkedFile>,
+    module_preloads: Vec<String>,
+    meta: &Markup,
+    nonce: Option<&str>,
+    import_map: Option<&ImportMap>,
+) -> (String, String) {
+    let manifest = MANIFEST.load();
+    let (body_files, head_files): (Vec<_>, Vec<_>) = files
+        .into_iter()
+        .partition(|file| file.placement == Placement::Body);
+    let (scripts, other_files): (Vec<_>, Vec<_>) = head_files
+        .into_iter()
+        .partition(|file| matches!(file.kind(), FileKind::Script | FileKind::Module));
+
+    let document = maud::html! {
         (DOCTYPE)
         html lang="en" {
             head {
@@ -18,21 +60,45 @@ pub fn html(
                 // <!-- title { "Wini's documentation" } -->
                 (meta)
 
-                @for style_sheet in style_sheets {
-                    link rel="stylesheet" href=(style_sheet);
+                @for file in other_files {
+                    (file.to_html(nonce))
                 }
                 link rel="icon" href="/favicon.ico" sizes="any";
                 link rel="icon" href="/favicon.svg" type="image/svg+xml";
-                link rel="stylesheet" href="/main.css";
+                link rel="stylesheet" href="/main.css" nonce=[nonce];
                 // script src="/helpers.min.js" defer {}
-                @for script in scripts_files {
-                    script src=(script) defer {}
+                @if let Some(import_map) = import_map {
+                    // Before the modules, so that it resolves all their imports
+                    script type="importmap" nonce=[nonce] { (PreEscaped(import_map.json())) }
+                    @for module in module_preloads {
+                        @let url = manifest.url(&module);
+                        link
+                            rel="modulepreload"
+                            href=(url)
+                            integrity=[import_map.integrity(url)]
+                            nonce=[nonce];
+                    }
+                }
+                @for script in scripts {
+                    (script.to_html(nonce))
+                }
+                @if *ENV_TYPE == EnvType::Local {
+                    script nonce=[nonce] { (PreEscaped(&*live_reload::CLIENT_SCRIPT)) }
                 }
             }
             body {
-                (PreEscaped(s))
+                (PreEscaped(BODY_MARKER))
+                @for file in body_files {
+                    (file.to_html(nonce))
+                }
             }
         }
     }
-    .into_string()
+    .into_string();
+
+    let (before, after) = document
+        .split_once(BODY_MARKER)
+        .expect("The marker is in the document");
+
+    (before.to_owned(), after.to_owned())
 }
@@ -23,7 +23,18 @@ pub static META_MAPPINGS: LazyLock<HashMap<&'static str, Vec<&'static str>>> =
         ])
     });
 
-pub fn add_meta_tags(res_parts: &mut Parts) -> Markup {
+/// The ID of a meta tag, so that it can be swapped out of band by htmx
+fn meta_id(name: &str) -> String {
+    format!("meta-{}", name.replace(':', "-"))
+}
+
+/// The title and the meta tags of a page.
+///
+/// If `out_of_band`, the meta tags are swapped by htmx into the ones of the current page, that have
+/// the same ID. htmx already updates the title of the page with the `<title>` of a response.
+pub fn add_meta_tags(res_parts: &mut Parts, out_of_band: bool) -> Markup {
+    let hx_swap_oob = out_of_band.then_some("true");
+
     if let Some(meta_tags) = res_parts.extensions.get::<Tags>() {
         html! {
             @if let Some(title) = meta_tags.get("title") {
@@ -32,17 +43,17 @@ pub fn add_meta_tags(res_parts: &mut Parts) -> Markup {
             @for (tag_name, tag_value) in meta_tags {
                 @if let Some(names) = META_MAPPINGS.get(tag_name) {
                     @for name in names {
-                          @if name.contains(':') {
-                            meta property=(name) content=(tag_value);
+                        @if name.contains(':') {
+                            meta id=(meta_id(name)) property=(name) content=(tag_value) hx-swap-oob=[hx_swap_oob];
                         } @else {
-                            meta name=(name) content=(tag_value);
+                            meta id=(meta_id(name)) name=(name) content=(tag_value) hx-swap-oob=[hx_swap_oob];
                         }
                     }
                 } @else {
                     @if tag_name.contains(':') {
-                        meta property=(tag_name) content=(tag_value);
+                        meta id=(meta_id(tag_name)) property=(tag_name) content=(tag_value) hx-swap-oob=[hx_swap_oob];
                     } @else {
-                        meta name=(tag_name) content=(tag_value);
+                        meta id=(meta_id(tag_name)) name=(tag_name) content=(tag_value) hx-swap-oob=[hx_swap_oob];
                     }
                 }
             }
@@ -1,163 +1,300 @@
 use {
     crate::{
-        concat_paths,
         shared::wini::{
+            assets::{self, ETAG_HASH_LEN},
+            bundle,
             config::SERVER_CONFIG,
-            dependencies::{normalize_relative_path, SCRIPTS_DEPENDENCIES},
-            err::{ServerErrorKind, ServerResult},
-            layer::Files,
-            packages_files::{VecOrString, PACKAGES_FILES},
-            PUBLIC_ENDPOINTS,
+            dependencies::{Dependency, SCRIPTS_DEPENDENCIES},
+            err::ServerResult,
+            layer::{self, Files, Fragment},
+            linked_file::{FileKind, LinkedFile},
+            packages_files::PACKAGES_FILES,
+            streaming::{Deferred, Streamed},
         },
-        utils::wini::buffer::buffer_to_string,
+        utils::wini::{buffer::buffer_to_string, security::CspNonce},
     },
     axum::{
-        body::Body,
+        body::{Body, Bytes},
         extract::Request,
         middleware::Next,
-        response::{IntoResponse, Response},
+        response::Response,
     },
-    hyper::header::{CONTENT_LENGTH, TRANSFER_ENCODING},
+    futures_util::{StreamExt, stream},
+    hyper::header::{CONTENT_LENGTH, ETAG, HeaderValue, TRANSFER_ENCODING, VARY},
+    itertools::Itertools,
+    maud::Markup,
     meta::add_meta_tags,
-    std::collections::HashSet,
-    tower_http::services::ServeFile,
 };
 
-mod html;
+pub mod html;
 mod meta;
 
 
 
 /// Use the basic template of HTML
 pub async fn template(req: Request, next: Next) -> ServerResult<Response> {
-    let path = &req.uri().path().to_string();
+    let nonce = req.extensions().get::<CspNonce>().cloned();
 
+    let is_fragment_request = layer::is_fragment_request(req.headers());
 
-    if (*PUBLIC_ENDPOINTS).contains(path) {
-        return Ok(ServeFile::new(format!("./public{path}"))
-            .try_call(req)
-            .await
-            .map_err(|_| ServerErrorKind::PublicRessourceNotFound(path.to_owned()))
-            .into_response());
+    // Compute the request, and collect the components it deferred
+    let (rep, deferred) = Deferred::scope(next.run(req)).await;
+
+    if is_fragment_request && layer::is_html(rep.headers()) {
+        return fragment(rep, deferred).await;
     }
 
-    // Compute the request
-    let rep = next.run(req).await;
-    let (mut res_parts, res_body) = rep.into_parts();
+    // The template only applies to full pages
+    if !layer::is_full_page(&rep, is_fragment_request) {
+        return resolve_deferred(rep, deferred).await;
+    }
 
-    let resp_str = buffer_to_string(res_body).await?;
+    let (mut res_parts, res_body) = rep.into_parts();
 
     // Extract the meta tags from the response headers
-    let meta_tags = add_meta_tags(&mut res_parts);
+    let meta_tags = add_meta_tags(&mut res_parts, false);
 
 
 
-    let (scripts, styles) = match res_parts.extensions.get::<Files>() {
+    let (files, module_preloads) = match res_parts.extensions.get::<Files>() {
         Some(files) => {
-            // Convert the string separated by ; into a vec
             let mut scripts = vec![];
             let mut styles = vec![];
+            let mut preloads = vec![];
 
             for file in files {
-                if !file.is_empty() {
-                    let formatted_file = format!("/{file}");
-                    if file.ends_with("css") {
-                        styles.push(formatted_file);
-                    } else if file.ends_with("js") {
-                        scripts.push(formatted_file);
-                    }
+                match file.kind() {
+                    FileKind::Script | FileKind::Module => scripts.push(file.clone()),
+                    FileKind::Stylesheet => styles.push(file.clone()),
+                    FileKind::Preload | FileKind::Font => preloads.push(file.clone()),
                 }
             }
 
-            let css_included_from_dependencies = order_scripts_by_dependent(&mut scripts);
+            // The browser resolves the imports of the modules, so they're only preloaded
+            let (module_preloads, css_included_from_dependencies) = if SERVER_CONFIG.scripts.modules
+            {
+                preload_modules(&scripts)
+            } else {
+                (Vec::new(), order_scripts_by_dependent(&mut scripts))
+            };
 
-            styles.extend(css_included_from_dependencies);
+            for style in css_included_from_dependencies {
+                if !styles.iter().any(|file| file.url == style.url) {
+                    styles.push(style);
+                }
+            }
 
-            (scripts, styles)
+            // The modules import each other by URL, so they can't be concatenated
+            let scripts = if SERVER_CONFIG.scripts.modules {
+                scripts
+            } else {
+                bundle::bundle(scripts).await
+            };
+
+            // The preloaded files first, so that they're downloaded as soon as possible
+            let files = preloads
+                .into_iter()
+                .chain(bundle::bundle(styles).await)
+                .chain(scripts)
+                .collect::<Vec<_>>();
+
+            (files, module_preloads)
         },
         None => (Vec::new(), Vec::new()),
     };
 
-    // Compute the HTML to send
-    let html = html::html(&resp_str, scripts, styles, &meta_tags);
+    let nonce = nonce.map(|nonce| nonce.0);
+    let linked_files = files.iter().map(|file| file.url.clone()).collect();
 
-    // Recalculate the length
-    *res_parts.headers.entry(CONTENT_LENGTH).or_insert(0.into()) = html.len().into();
+    // Without deferred components, the page is known before being sent, so it can be revalidated,
+    // unless it carries the nonce of the request
+    let is_streamed = !deferred.is_empty();
+    let res_body = if is_streamed || SERVER_CONFIG.security.uses_nonce() {
+        res_body
+    } else {
+        let content = buffer_to_string(res_body).await?;
+        let etag = etag(files.clone(), module_preloads.clone(), &meta_tags, &content);
+        res_parts
+            .headers
+            .entry(ETAG)
+            .or_insert(HeaderValue::from_str(&etag)?);
+        Body::from(content)
+    };
+
+    // The document around the content of the page
+    let (before_body, after_body) =
+        html::html(files, module_preloads, &meta_tags, nonce.as_deref());
+
+    // The `<head>` is sent first, then the content of the page, and then the deferred components
+    // once they're ready
+    let body = stream::once(async { Ok(Bytes::from(before_body)) })
+        .chain(res_body.into_data_stream())
+        .chain(
+            deferred
+                .stream(nonce, linked_files)
+                .map(|chunk| Ok(Bytes::from(chunk))),
+        )
+        .chain(stream::once(async { Ok(Bytes::from(after_body)) }));
 
+    // The length isn't known in advance
+    res_parts.headers.remove(CONTENT_LENGTH);
     res_parts.headers.remove(TRANSFER_ENCODING);
+    res_parts
+        .headers
+        .append(VARY, HeaderValue::from_static(layer::VARY_HEADERS));
 
-    let res = Response::from_parts(res_parts, Body::from(html));
+    if is_streamed {
+        res_parts.extensions.insert(Streamed);
+    }
+
+    let res = Response::from_parts(res_parts, Body::from_stream(body));
 
 
     Ok(res)
 }
 
+/// A weak `ETag` of a page: the nonces change with each response, but they aren't used by the
+/// Content-Security-Policy.
+fn etag(
+    files: Vec<LinkedFile>,
+    module_preloads: Vec<String>,
+    meta_tags: &Markup,
+    content: &str,
+) -> String {
+    let (before_body, after_body) = html::html(files, module_preloads, meta_tags, None);
+
+    format!(
+        "W/\"{}\"",
+        assets::hash(
+            [before_body, content.to_owned(), after_body]
+                .concat()
+                .as_bytes(),
+            ETAG_HASH_LEN
+        )
+    )
+}
+
+/// The answer to a fragment request: only the content of the page, with its title and meta tags
+/// swapped out of band
+async fn fragment(rep: Response, deferred: Deferred) -> ServerResult<Response> {
+    // The responses that are already complete, such as the pages of errors outside of the pages, are
+    // sent as is
+    if rep.status().is_redirection() || rep.extensions().get::<Fragment>().is_some() {
+        return resolve_deferred(rep, deferred).await;
+    }
+
+    let (mut res_parts, res_body) = rep.into_parts();
+
+    let mut html = deferred.resolve(buffer_to_string(res_body).await?).await;
+    html.push_str(&add_meta_tags(&mut res_parts, true).into_string());
+
+    *res_parts.headers.entry(CONTENT_LENGTH).or_insert(0.into()) = html.len().into();
+    res_parts.headers.remove(TRANSFER_ENCODING);
+    res_parts
+        .headers
+        .append(VARY, HeaderValue::from_static(layer::VARY_HEADERS));
+
+    Ok(Response::from_parts(res_parts, Body::from(html)))
+}
+
+/// Put the deferred components into a response that isn't streamed
+async fn resolve_deferred(rep: Response, deferred: Deferred) -> ServerResult<Response> {
+    if deferred.is_empty() {
+        return Ok(rep);
+    }
+
+    let (mut res_parts, res_body) = rep.into_parts();
+    let html = deferred.resolve(buffer_to_string(res_body).await?).await;
+
+    *res_parts.headers.entry(CONTENT_LENGTH).or_insert(0.into()) = html.len().into();
+    res_parts.headers.remove(TRANSFER_ENCODING);
+
+    Ok(Response::from_parts(res_parts, Body::from(html)))
+}
 
-fn order_scripts_by_dependent(scripts: &mut Vec<String>) -> HashSet<String> {
+
+/// Put the scripts after the ones they import, and add the files of the packages they import.
+/// Returns the styles of these packages.
+fn order_scripts_by_dependent(scripts: &mut Vec<LinkedFile>) -> Vec<LinkedFile> {
     // The css that is linked to a javascript package, and that therefore, should also be included
-    let mut css_included_from_dependencies: HashSet<String> = HashSet::new();
-    let mut packages = Vec::<String>::new();
-
-    // Get all dependencies
-    let dependencies = scripts
-        .iter()
-        .filter_map(|script| (*SCRIPTS_DEPENDENCIES).get(script))
-        .filter_map(std::clone::Clone::clone)
-        .flatten()
-        .map(|dep| {
-            let public_path =
-                normalize_relative_path(concat_paths!("str", &SERVER_CONFIG.path.public))
-                    .display()
-                    .to_string();
-
-            if dep.starts_with(&public_path) {
-                dep[SERVER_CONFIG.path.public.len() - 3..].to_string()
-            } else {
-                if !dep.ends_with(".js") {
-                    packages.push(dep.clone());
-                }
-                dep
-            }
-        })
-        .collect::<Vec<String>>();
+    let mut css_included_from_dependencies = Vec::new();
+    let mut ordered = Vec::new();
 
-    // Pop the dependencies at the top
-    for dep in dependencies {
-        if scripts.contains(&dep) {
-            scripts.retain(|script| *script != dep);
-        }
-        if !packages.contains(&dep) {
-            scripts.push(dep.clone());
+    let scripts_dependencies = SCRIPTS_DEPENDENCIES.load();
+    for dependency in scripts_dependencies.order(scripts.iter().map(|file| file.url.as_str())) {
+        match dependency {
+            // The scripts of the page keep their options
+            Dependency::Script(url) => {
+                ordered.push(
+                    scripts
+                        .iter()
+                        .find(|file| file.url == url)
+                        .cloned()
+                        .unwrap_or_else(|| LinkedFile::new(url)),
+                );
+            },
+            Dependency::Package(pkg) => {
+                for file in package_files(&pkg) {
+                    if file.kind() == FileKind::Stylesheet {
+                        css_included_from_dependencies.push(file.clone());
+                    } else {
+                        ordered.push(file.clone());
+                    }
+                }
+            },
         }
     }
 
-    for pkg in packages {
-        match (*PACKAGES_FILES).get(&pkg) {
-            Some(VecOrString::String(file)) => {
-                if file.ends_with(".css") {
-                    css_included_from_dependencies.insert(file.to_owned());
-                } else {
-                    scripts.push(file.to_owned());
+    // A file of a package imported by several scripts is linked once, before the first one
+    *scripts = ordered
+        .into_iter()
+        .unique_by(|file| file.url.clone())
+        .collect();
+
+    css_included_from_dependencies
+}
+
+/// The modules imported by the scripts, that aren't linked in the page, to preload them. Returns
+/// them with the styles of the packages they import.
+fn preload_modules(scripts: &[LinkedFile]) -> (Vec<String>, Vec<LinkedFile>) {
+    let mut css_included_from_dependencies = Vec::new();
+    let mut preloads = Vec::new();
+
+    let scripts_dependencies = SCRIPTS_DEPENDENCIES.load();
+    for dependency in scripts_dependencies.order(scripts.iter().map(|file| file.url.as_str())) {
+        match dependency {
+            Dependency::Script(url) => {
+                if !scripts.iter().any(|file| file.url == url) {
+                    preloads.push(url);
                 }
             },
-            Some(VecOrString::Vec(files)) => {
-                for file in files {
-                    if file.ends_with(".css") {
-                        css_included_from_dependencies.insert(file.to_owned());
+            Dependency::Package(pkg) => {
+                for file in package_files(&pkg) {
+                    if file.kind() == FileKind::Stylesheet {
+                        css_included_from_dependencies.push(file.clone());
                     } else {
-                        scripts.push(file.to_owned());
+                        preloads.push(file.url.clone());
                     }
                 }
             },
-            None => {
-                log::warn!(
-                    "The package {pkg:#?} doesn't have any associated minified file. Therefore, nothing will be send for this package."
-                );
-            },
         }
     }
 
-    scripts.reverse();
+    (
+        preloads.into_iter().unique().collect(),
+        css_included_from_dependencies,
+    )
+}
 
-    css_included_from_dependencies
+/// The files of a package of `packages-files.toml`
+fn package_files(pkg: &str) -> &'static [LinkedFile] {
+    match (*PACKAGES_FILES).get(pkg) {
+        Some(files) => files.as_slice(),
+        None => {
+            log::warn!(
+                "The package {pkg:#?} doesn't have any associated minified file. Therefore, nothing will be send for this package."
+            );
+            &[]
+        },
+    }
 }
@@ -0,0 +1,73 @@
+use {
+    crate::shared::wini::{health, metrics},
+    axum::{
+        Router,
+        extract::{MatchedPath, Request},
+        http::HeaderValue,
+        middleware::Next,
+        response::{IntoResponse, Response},
+        routing::get,
+    },
+    hyper::{StatusCode, header::CONTENT_TYPE},
+    std::time::Instant,
+};
+
+/// The endpoints used by orchestrators and monitoring:
+/// - `/metrics`: metrics in the Prometheus text format
+/// - `/healthz`: liveness probe
+/// - `/readyz`: readiness probe
+pub fn router() -> Router {
+    Router::new()
+        .route("/metrics", get(metrics))
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+}
+
+async fn metrics() -> Response {
+    (
+        [(
+            CONTENT_TYPE,
+            HeaderValue::from_static("text/plain; version=0.0.4; charset=utf-8"),
+        )],
+        metrics::encode().await,
+    )
+        .into_response()
+}
+
+async fn healthz() -> StatusCode {
+    StatusCode::OK
+}
+
+async fn readyz() -> StatusCode {
+    if health::is_ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+/// Answer `503 Service Unavailable` until the server is ready
+pub async fn reject_until_ready(req: Request, next: Next) -> Response {
+    if health::is_ready() {
+        next.run(req).await
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE.into_response()
+    }
+}
+
+/// Count the requests and measure their latency, per route
+pub async fn track_requests(req: Request, next: Next) -> Response {
+    let method = req.method().to_string();
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| "<unmatched>".to_owned(), |path| path.as_str().to_owned());
+
+    let start = Instant::now();
+    let resp = next.run(req).await;
+
+    metrics::HTTP_REQUEST_DURATION_SECONDS.observe(&[&method, &route], start.elapsed());
+    metrics::HTTP_REQUESTS_TOTAL.inc(&[&method, &route, resp.status().as_str()]);
+
+    resp
+}
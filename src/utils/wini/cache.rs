@@ -1,7 +1,30 @@
 use {
-    crate::shared::wini::{cache::CacheCategory, config::SERVER_CONFIG, err::ServerResult},
-    axum::{http::HeaderValue, middleware::Next, response::Response},
-    hyper::header::CACHE_CONTROL,
+    crate::shared::wini::{
+        assets::Validators,
+        cache::CacheCategory,
+        config::SERVER_CONFIG,
+        err::ServerResult,
+        layer,
+    },
+    axum::{
+        extract::Request,
+        http::{HeaderMap, HeaderValue},
+        middleware::Next,
+        response::Response,
+    },
+    hyper::{
+        Method,
+        StatusCode,
+        header::{
+            CACHE_CONTROL,
+            CONTENT_LENGTH,
+            ETAG,
+            IF_MODIFIED_SINCE,
+            IF_NONE_MATCH,
+            LAST_MODIFIED,
+            TRANSFER_ENCODING,
+        },
+    },
 };
 
 /// Add cache to an axum response
@@ -13,17 +36,123 @@ pub fn add_cache(mut response: Response, cache_rule: &str) -> ServerResult<Respo
     Ok(response)
 }
 
-/// Add the HTML cache rule
+/// Add the HTML cache rule to the HTML responses
 pub async fn html_middleware(
     req: hyper::Request<axum::body::Body>,
     next: Next,
 ) -> ServerResult<Response> {
     let rep = next.run(req).await;
     let (mut res_parts, res_body) = rep.into_parts();
-    res_parts.headers.insert(
-        CACHE_CONTROL,
-        HeaderValue::from_str(&SERVER_CONFIG.cache.get_or_panic(CacheCategory::Html))?,
-    );
+
+    // Errors shouldn't be cached like the pages
+    if layer::is_html(&res_parts.headers) && !res_parts.status.is_server_error() {
+        res_parts.headers.insert(
+            CACHE_CONTROL,
+            HeaderValue::from_str(&SERVER_CONFIG.cache.get_or_panic(CacheCategory::Html))?,
+        );
+    }
     let res = Response::from_parts(res_parts, res_body);
     Ok(res)
 }
+
+/// Add the validators of a file to its response
+pub fn add_validators(mut response: Response, validators: &Validators) -> ServerResult<Response> {
+    let headers = response.headers_mut();
+    headers.insert(ETAG, HeaderValue::from_str(&validators.etag)?);
+
+    if let Some(last_modified) = validators.last_modified {
+        headers.insert(
+            LAST_MODIFIED,
+            HeaderValue::from_str(&httpdate::fmt_http_date(last_modified))?,
+        );
+    }
+
+    Ok(response)
+}
+
+/// Answer `304 Not Modified`, without the body, when the validators of the request
+/// (`If-None-Match`, or else `If-Modified-Since`) match the ones of the response.
+///
+/// The responses whose cache rule is `no-store` can't be revalidated, so their validators are
+/// removed. Neither can the HTML responses when the Content-Security-Policy uses a nonce: the
+/// browser would keep the page with the nonce of a previous response.
+pub async fn conditional_requests(req: Request, next: Next) -> Response {
+    let is_safe = matches!(*req.method(), Method::GET | Method::HEAD);
+    let if_none_match = req.headers().get(IF_NONE_MATCH).cloned();
+    let if_modified_since = req.headers().get(IF_MODIFIED_SINCE).cloned();
+
+    let mut resp = next.run(req).await;
+
+    if is_no_store(resp.headers()) ||
+        (layer::is_html(resp.headers()) && SERVER_CONFIG.security.uses_nonce())
+    {
+        resp.headers_mut().remove(ETAG);
+        resp.headers_mut().remove(LAST_MODIFIED);
+        return resp;
+    }
+
+    if !is_safe || resp.status() != StatusCode::OK {
+        return resp;
+    }
+
+    let is_not_modified = match (if_none_match, if_modified_since) {
+        (Some(if_none_match), _) => etag_matches(resp.headers(), &if_none_match),
+        (None, Some(if_modified_since)) => is_modified_before(resp.headers(), &if_modified_since),
+        (None, None) => false,
+    };
+
+    if !is_not_modified {
+        return resp;
+    }
+
+    let (mut res_parts, _) = resp.into_parts();
+    res_parts.status = StatusCode::NOT_MODIFIED;
+    res_parts.headers.remove(CONTENT_LENGTH);
+    res_parts.headers.remove(TRANSFER_ENCODING);
+
+    Response::from_parts(res_parts, axum::body::Body::empty())
+}
+
+fn is_no_store(headers: &HeaderMap) -> bool {
+    headers
+        .get(CACHE_CONTROL)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|rule| {
+            rule.split(',')
+                .any(|directive| directive.trim().eq_ignore_ascii_case("no-store"))
+        })
+}
+
+/// Whether one of the `ETag`s of `If-None-Match` is the one of the response. They are compared
+/// weakly, since the compression doesn't change the content.
+fn etag_matches(headers: &HeaderMap, if_none_match: &HeaderValue) -> bool {
+    let Some(etag) = headers.get(ETAG).and_then(|etag| etag.to_str().ok()) else {
+        return false;
+    };
+    let weak = |etag: &str| etag.trim().trim_start_matches("W/").to_owned();
+
+    if_none_match.to_str().is_ok_and(|if_none_match| {
+        if_none_match.trim() == "*" ||
+            if_none_match
+                .split(',')
+                .any(|candidate| weak(candidate) == weak(etag))
+    })
+}
+
+/// Whether the response was last modified before `If-Modified-Since`
+fn is_modified_before(headers: &HeaderMap, if_modified_since: &HeaderValue) -> bool {
+    let date = |value: &HeaderValue| {
+        value
+            .to_str()
+            .ok()
+            .and_then(|value| httpdate::parse_http_date(value).ok())
+    };
+
+    match (
+        headers.get(LAST_MODIFIED).and_then(date),
+        date(if_modified_since),
+    ) {
+        (Some(last_modified), Some(since)) => last_modified <= since,
+        _ => false,
+    }
+}
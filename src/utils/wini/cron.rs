@@ -25,3 +25,28 @@ impl HumanDuration for u64 {
         Duration::from_secs(self * 60 * 60 * 24)
     }
 }
+
+
+/// The outcome of a cron, used to know if a run has failed.
+///
+/// A cron returning `()` never fails, and a cron returning a `Result` fails on `Err`.
+pub trait CronOutcome {
+    fn is_failure(&self) -> bool;
+}
+
+impl CronOutcome for () {
+    fn is_failure(&self) -> bool {
+        false
+    }
+}
+
+impl<T, E: std::fmt::Debug> CronOutcome for Result<T, E> {
+    fn is_failure(&self) -> bool {
+        if let Err(err) = self {
+            log::error!("Cron failed: {err:?}");
+            true
+        } else {
+            false
+        }
+    }
+}
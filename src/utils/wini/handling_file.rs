@@ -1,75 +1,261 @@
 use {
-    crate::shared::wini::{
-        cache::{AddCache, CacheCategory},
-        config::SERVER_CONFIG,
-        err::{ServerErrorKind, ServerResult},
-        CSS_FILES,
-        JS_FILES,
-        PUBLIC_ENDPOINTS,
+    crate::{
+        shared::wini::{
+            assets::{MANIFEST, Validators},
+            bundle::{self, BUNDLES, Bundle},
+            cache::{AddCache, CacheCategory},
+            config::SERVER_CONFIG,
+            err::{ServerErrorKind, ServerResult},
+            precompressed::{PRECOMPRESSED, Precompressed, StaticFile},
+            CSS_FILES,
+            JS_FILES,
+            PUBLIC_ENDPOINTS,
+        },
+        utils::wini::cache::add_validators,
     },
     axum::{
         extract::Request,
-        http::HeaderValue,
+        http::{HeaderMap, HeaderValue},
+        middleware::Next,
         response::{AppendHeaders, IntoResponse, Response},
     },
-    hyper::{header::CONTENT_TYPE, StatusCode},
+    hyper::header::{ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_TYPE, RANGE, VARY},
     tower_http::services::ServeFile,
 };
 
-/// This request handler is in charge of serving files.
+/// This middleware is in charge of serving files.
 /// In particular, these kind of files, in that order:
+/// - bundles (the css or javascript files of a page, concatenated)
+/// - fingerprinted files (css, javascript and packages files, with the hash of their content)
 /// - public files (exposed files)
 /// - css files
 /// - javascript files
-pub async fn handle_file(req: Request) -> ServerResult<Response<axum::body::Body>> {
+///
+/// The files are sent precompressed when the client accepts it.
+///
+/// The other requests are given to the next service.
+pub async fn handle_file(req: Request, next: Next) -> ServerResult<Response<axum::body::Body>> {
     let path = &req.uri().path().to_string();
 
-    if PUBLIC_ENDPOINTS.contains(path) {
-        return Ok(ServeFile::new(format!("./public{path}"))
-            .try_call(req)
-            .await
-            .map_err(|_| ServerErrorKind::PublicRessourceNotFound(path.clone()))?
-            .into_response());
+    if path.starts_with(bundle::ROUTE) &&
+        let Some(bundle) = BUNDLES.load().built(path)
+    {
+        return bundle_into_response(&bundle, req.headers())?.add_cache(
+            &SERVER_CONFIG
+                .cache
+                .get_or_panic(CacheCategory::Fingerprinted),
+        );
     }
 
-    if path.ends_with(".css") {
-        return if let Some(file) = CSS_FILES.get(path) {
-            css_into_response(file)
-        } else {
-            Err(StatusCode::NOT_FOUND.into())
-        };
+    if let Some(url) = MANIFEST.load().file(path).map(ToOwned::to_owned) {
+        let fingerprinted_cache = SERVER_CONFIG
+            .cache
+            .get_or_panic(CacheCategory::Fingerprinted);
+
+        if let Some(file) = CSS_FILES.load().get(&url) {
+            return file_into_response(&url, file, "css", req.headers())?
+                .add_cache(&fingerprinted_cache);
+        }
+
+        if let Some(file) = JS_FILES.load().get(&url) {
+            return file_into_response(&url, file, "javascript", req.headers())?
+                .add_cache(&fingerprinted_cache);
+        }
+
+        return public_file_into_response(req, &url)
+            .await?
+            .add_cache(&fingerprinted_cache);
+    }
+
+    if PUBLIC_ENDPOINTS.load().contains(path) {
+        return public_file_into_response(req, path).await;
     }
 
-    if path.ends_with(".js") {
-        return if let Some(file) = JS_FILES.get(path) {
-            js_into_response(file)
-        } else {
-            Err(StatusCode::NOT_FOUND.into())
-        };
+    if let Some(file) = CSS_FILES.load().get(path) {
+        return css_into_response(path, file, req.headers());
     }
 
-    Err(StatusCode::NOT_FOUND.into())
+    if let Some(file) = JS_FILES.load().get(path) {
+        return js_into_response(path, file, req.headers());
+    }
+
+    Ok(next.run(req).await)
 }
 
-fn js_into_response(file_content: &str) -> ServerResult<Response<axum::body::Body>> {
-    file_into_response(file_content, "javascript")?
+fn js_into_response(
+    url: &str,
+    file_content: &str,
+    headers: &HeaderMap,
+) -> ServerResult<Response<axum::body::Body>> {
+    file_into_response(url, file_content, "javascript", headers)?
         .add_cache(&SERVER_CONFIG.cache.get_or_panic(CacheCategory::Javascript))
 }
 
-fn css_into_response(file_content: &str) -> ServerResult<Response<axum::body::Body>> {
-    file_into_response(file_content, "css")?
+fn css_into_response(
+    url: &str,
+    file_content: &str,
+    headers: &HeaderMap,
+) -> ServerResult<Response<axum::body::Body>> {
+    file_into_response(url, file_content, "css", headers)?
         .add_cache(&SERVER_CONFIG.cache.get_or_panic(CacheCategory::Css))
 }
 
 /// Create a response from the content of the file and add the content_type header accordingly with
-/// the kind of content_type passed in parameter of this function.
-fn file_into_response(file_content: &str, kind: &str) -> ServerResult<Response<axum::body::Body>> {
-    Ok((
-        [(
-            CONTENT_TYPE,
-            HeaderValue::from_str(&format!("text/{kind}; charset=utf-8"))?,
-        )],
-        Box::<str>::from(file_content),
+/// the kind of content_type passed in parameter of this function. Its validators, computed when it
+/// was loaded, are added.
+fn file_into_response(
+    url: &str,
+    file_content: &str,
+    kind: &str,
+    headers: &HeaderMap,
+) -> ServerResult<Response<axum::body::Body>> {
+    let content_type = HeaderValue::from_str(&format!("text/{kind}; charset=utf-8"))?;
+    let precompressed = PRECOMPRESSED.load();
+    let manifest = MANIFEST.load();
+    let (precompressed, validators) = (
+        precompressed.get(url).map(|file| &file.precompressed),
+        manifest.validators(url),
+    );
+
+    if let Some(resp) =
+        precompressed_into_response(precompressed, validators, content_type.clone(), headers)?
+    {
+        return Ok(resp);
+    }
+
+    static_file(
+        (
+            [(CONTENT_TYPE, content_type)],
+            Box::<str>::from(file_content),
+        )
+            .into_response(),
+        precompressed.is_some(),
+        validators,
+    )
+}
+
+/// The content of a bundle
+fn bundle_into_response(
+    bundle: &Bundle,
+    headers: &HeaderMap,
+) -> ServerResult<Response<axum::body::Body>> {
+    let content_type = HeaderValue::from_static(bundle.content_type);
+
+    if let Some(resp) = precompressed_into_response(
+        Some(&bundle.precompressed),
+        Some(&bundle.validators),
+        content_type.clone(),
+        headers,
+    )? {
+        return Ok(resp);
+    }
+
+    static_file(
+        ([(CONTENT_TYPE, content_type)], bundle.content.clone()).into_response(),
+        true,
+        Some(&bundle.validators),
+    )
+}
+
+/// Serve a file of `./public`, from the disk if the client doesn't accept any of its precompressed
+/// versions. The parts of files (`Range`) are always served from the disk.
+///
+/// The precompressed files have the validators computed when they were compressed, since only the
+/// files of the packages are in the manifest.
+async fn public_file_into_response(
+    req: Request,
+    url: &str,
+) -> ServerResult<Response<axum::body::Body>> {
+    let precompressed = PRECOMPRESSED.load();
+    let manifest = MANIFEST.load();
+    let file = precompressed.get(url);
+    let (precompressed, validators) = (
+        file.map(|file| &file.precompressed),
+        manifest
+            .validators(url)
+            .or_else(|| file.map(|file| &file.validators)),
+    );
+
+    if !req.headers().contains_key(RANGE) {
+        let content_type =
+            HeaderValue::from_str(mime_guess::from_path(url).first_or_octet_stream().as_ref())?;
+
+        if let Some(resp) =
+            precompressed_into_response(precompressed, validators, content_type, req.headers())?
+        {
+            return Ok(resp);
+        }
+    }
+
+    // The file sets its `Last-Modified`
+    let resp = ServeFile::new(format!("./public{url}"))
+        .try_call(req)
+        .await
+        .map_err(|_| ServerErrorKind::PublicRessourceNotFound(url.to_owned()))?
+        .into_response();
+
+    if resp.status().is_success() {
+        static_file(resp, precompressed.is_some(), validators)
+    } else {
+        Ok(resp)
+    }
+}
+
+/// The precompressed version of the file in the best encoding accepted by the client, if there is
+/// one
+fn precompressed_into_response(
+    precompressed: Option<&Precompressed>,
+    validators: Option<&Validators>,
+    content_type: HeaderValue,
+    headers: &HeaderMap,
+) -> ServerResult<Option<Response<axum::body::Body>>> {
+    if !headers.contains_key(ACCEPT_ENCODING) {
+        return Ok(None);
+    }
+
+    let Some((encoding, compressed)) =
+        precompressed.and_then(|precompressed| precompressed.best(headers))
+    else {
+        return Ok(None);
+    };
+
+    let mut resp = (
+        [
+            (CONTENT_TYPE, content_type),
+            (
+                CONTENT_ENCODING,
+                HeaderValue::from_static(encoding.as_str()),
+            ),
+            (VARY, HeaderValue::from_static("accept-encoding")),
+        ],
+        compressed,
     )
-        .into_response())
+        .into_response();
+    resp.extensions_mut().insert(StaticFile);
+
+    match validators {
+        Some(validators) => add_validators(resp, &validators.for_encoding(encoding)).map(Some),
+        None => Ok(Some(resp)),
+    }
+}
+
+/// Mark the response of a file sent uncompressed, so that it isn't compressed again, and add its
+/// validators
+fn static_file(
+    mut resp: Response<axum::body::Body>,
+    is_precompressed: bool,
+    validators: Option<&Validators>,
+) -> ServerResult<Response<axum::body::Body>> {
+    resp.extensions_mut().insert(StaticFile);
+
+    // The response would have been another one with another `Accept-Encoding`
+    if is_precompressed {
+        resp.headers_mut()
+            .append(VARY, HeaderValue::from_static("accept-encoding"));
+    }
+
+    match validators {
+        Some(validators) => add_validators(resp, validators),
+        None => Ok(resp),
+    }
 }
@@ -0,0 +1,310 @@
+//! Live reload of the files served by the server, in the `Local` environment.
+//!
+//! The files of `src/`, `public/` and `wini.toml` are watched. When they change, the files kept in
+//! memory are read again, and the browser is told to refresh the affected stylesheets or the whole
+//! page over Server-Sent Events.
+//!
+//! Changes to Rust files are not handled here: the server has to be compiled again. The server
+//! also has to be restarted for the changes to `wini.toml` to be applied.
+
+use {
+    crate::shared::wini::{
+        CSS_FILES,
+        ENV_TYPE,
+        JS_FILES,
+        PUBLIC_ENDPOINTS,
+        assets::{self, MANIFEST},
+        bundle::BUNDLES,
+        dependencies::SCRIPTS_DEPENDENCIES,
+        env::EnvType,
+        import_map::IMPORT_MAP,
+        precompressed::PRECOMPRESSED,
+    },
+    axum::{
+        Router,
+        response::sse::{Event, KeepAlive, Sse},
+        routing::get,
+    },
+    futures_util::{Stream, stream},
+    notify::{EventKind, RecursiveMode, Watcher},
+    std::{
+        collections::HashSet,
+        convert::Infallible,
+        path::{Path, PathBuf},
+        sync::{LazyLock, mpsc},
+        time::Duration,
+    },
+    tokio::sync::broadcast::{self, error::RecvError},
+};
+
+
+/// The endpoint on which the browser listens to the changes
+pub const ENDPOINT: &str = "/__wini/live-reload";
+
+/// The script injected in the pages to listen to the changes.
+///
+/// When the connection is opened again after an error, the server restarted, so the page is
+/// refreshed. The stylesheets are matched without their fingerprint, since it changes with their
+/// content.
+pub static CLIENT_SCRIPT: LazyLock<String> = LazyLock::new(|| {
+    // The hash of the fingerprints is in hexadecimal
+    let fingerprint_len = assets::HASH_LEN * 2;
+
+    format!(
+        r#"(() => {{
+  const changes = new EventSource("{ENDPOINT}");
+  let wasDisconnected = false;
+
+  changes.addEventListener("open", () => {{
+    if (wasDisconnected) location.reload();
+  }});
+  changes.addEventListener("error", () => {{
+    wasDisconnected = true;
+  }});
+
+  changes.addEventListener("reload", () => location.reload());
+  const withoutFingerprint = (path) => path.replace(/\.[0-9a-f]{{{fingerprint_len}}}(\.css)$/, "$1");
+
+  changes.addEventListener("css", (event) => {{
+    const stylesheet = JSON.parse(event.data);
+    for (const link of document.querySelectorAll('link[rel="stylesheet"]')) {{
+      const url = new URL(link.href);
+      if (withoutFingerprint(url.pathname) === withoutFingerprint(stylesheet.url)) {{
+        url.pathname = stylesheet.url;
+        url.searchParams.set("t", Date.now().toString());
+        // The previous integrity would block the new content
+        if (stylesheet.integrity) link.integrity = stylesheet.integrity;
+        else link.removeAttribute("integrity");
+        link.href = url.toString();
+      }}
+    }}
+  }});
+}})();"#
+    )
+});
+
+/// The directory of the public files
+const PUBLIC_DIR: &str = "public";
+
+/// The files and directories watched
+const WATCHED: [&str; 3] = ["src", PUBLIC_DIR, "wini.toml"];
+
+/// How long to wait for other changes, since editors often write several times in a row
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+
+/// What the browser should refresh
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Change {
+    /// Only the stylesheet at this URL, with its new integrity
+    Css {
+        url: String,
+        integrity: Option<String>,
+    },
+    /// The whole page
+    Page,
+}
+
+impl Change {
+    fn into_event(self) -> Event {
+        match self {
+            Self::Css { url, integrity } => {
+                Event::default()
+                    .event("css")
+                    .data(serde_json::json!({ "url": url, "integrity": integrity }).to_string())
+            },
+            Self::Page => Event::default().event("reload").data("page"),
+        }
+    }
+}
+
+static CHANGES: LazyLock<broadcast::Sender<Change>> = LazyLock::new(|| broadcast::channel(16).0);
+
+
+/// Start watching the files, if the environment is `Local`.
+///
+/// `reloads` are the data to read again when a file with this extension changes, in addition to
+/// the CSS, JavaScript and public files.
+///
+/// # Example
+///
+/// ```ignore
+/// live_reload::watch(&[("md", || pages::doc::PAGES.reload())]);
+/// ```
+pub fn watch(reloads: &'static [(&'static str, fn())]) {
+    if *ENV_TYPE != EnvType::Local {
+        return;
+    }
+
+    let (sender, receiver) = mpsc::channel();
+
+    let mut watcher = match notify::recommended_watcher(sender) {
+        Ok(watcher) => watcher,
+        Err(err) => {
+            log::warn!("Couldn't start the live reload: {err}");
+            return;
+        },
+    };
+
+    for path in WATCHED {
+        if let Err(err) = watcher.watch(Path::new(path), RecursiveMode::Recursive) {
+            log::warn!("Couldn't watch `{path}` for the live reload: {err}");
+        }
+    }
+
+    std::thread::spawn(move || {
+        // The watcher stops when it's dropped
+        let _watcher = watcher;
+        let current_dir = std::env::current_dir().unwrap_or_default();
+
+        while let Ok(event) = receiver.recv() {
+            let mut paths = HashSet::new();
+            let mut event = Some(event);
+
+            while let Some(received) = event {
+                if let Ok(received) = received &&
+                    !matches!(received.kind, EventKind::Access(_))
+                {
+                    paths.extend(received.paths.into_iter().map(|path| {
+                        path.strip_prefix(&current_dir)
+                            .map(Path::to_path_buf)
+                            .unwrap_or(path)
+                    }));
+                }
+
+                event = receiver.recv_timeout(DEBOUNCE).ok();
+            }
+
+            for change in reload(&paths, reloads) {
+                // There is an error only if no browser is listening
+                let _ = CHANGES.send(change);
+            }
+        }
+    });
+}
+
+
+/// Read again the data affected by the changed files, and return what the browser should refresh
+fn reload(paths: &HashSet<PathBuf>, reloads: &[(&str, fn())]) -> HashSet<Change> {
+    let mut changes = HashSet::new();
+    let (mut css_files, mut js_files, mut public_endpoints) = (false, false, false);
+    let mut hooks = Vec::new();
+
+    for path in paths {
+        let extension = path.extension().and_then(|ext| ext.to_str());
+        let url = format!("/{}", path.display());
+
+        if let Ok(public_path) = path.strip_prefix(PUBLIC_DIR) {
+            public_endpoints = true;
+            changes.insert(
+                if extension == Some("css") {
+                    Change::Css {
+                        url: format!("/{}", public_path.display()),
+                        integrity: None,
+                    }
+                } else {
+                    Change::Page
+                },
+            );
+        } else if path == Path::new("wini.toml") {
+            log::warn!("Live reload: `wini.toml` changed, restart the server to apply it");
+        } else {
+            match extension {
+                Some("css") => {
+                    css_files = true;
+                    changes.insert(Change::Css {
+                        url,
+                        integrity: None,
+                    });
+                },
+                Some("js") => {
+                    js_files = true;
+                    changes.insert(Change::Page);
+                },
+                Some(extension) => {
+                    if let Some((_, hook)) = reloads.iter().find(|(ext, _)| *ext == extension) {
+                        hooks.push(*hook);
+                        changes.insert(Change::Page);
+                    }
+                },
+                None => {},
+            }
+        }
+    }
+
+    let mut to_reload = Vec::<fn()>::new();
+    if css_files {
+        to_reload.push(|| CSS_FILES.reload());
+    }
+    if js_files {
+        to_reload.push(|| JS_FILES.reload());
+        to_reload.push(|| SCRIPTS_DEPENDENCIES.reload());
+    }
+    if public_endpoints {
+        to_reload.push(|| PUBLIC_ENDPOINTS.reload());
+    }
+    if css_files || js_files || public_endpoints {
+        to_reload.push(|| MANIFEST.reload());
+        to_reload.push(|| PRECOMPRESSED.reload());
+        to_reload.push(|| BUNDLES.reload());
+        to_reload.push(|| IMPORT_MAP.reload());
+    }
+    hooks.dedup();
+    to_reload.extend(hooks);
+
+    // A file that is invalid, or removed while it's saved, shouldn't stop the watcher: the previous
+    // data is kept until the file changes again
+    for reload in to_reload {
+        if std::panic::catch_unwind(reload).is_err() {
+            log::error!("Live reload: couldn't read the changed files again");
+        }
+    }
+
+    if !changes.is_empty() {
+        log::info!("Live reload: {} file(s) changed", paths.len());
+    }
+
+    // Refreshing the stylesheets is useless if the whole page is refreshed
+    if changes.contains(&Change::Page) {
+        HashSet::from([Change::Page])
+    } else {
+        let manifest = MANIFEST.load();
+        changes
+            .into_iter()
+            .map(|change| {
+                match change {
+                    Change::Css { url, .. } => {
+                        Change::Css {
+                            integrity: manifest.integrity(&url).map(ToOwned::to_owned),
+                            url: manifest.url(&url).to_owned(),
+                        }
+                    },
+                    Change::Page => Change::Page,
+                }
+            })
+            .collect()
+    }
+}
+
+
+/// The router serving the changes to the browser
+pub fn router() -> Router {
+    Router::new().route(ENDPOINT, get(changes))
+}
+
+async fn changes() -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let stream = stream::unfold(CHANGES.subscribe(), |mut receiver| {
+        async move {
+            let change = match receiver.recv().await {
+                Ok(change) => change,
+                // Some changes were missed
+                Err(RecvError::Lagged(_)) => Change::Page,
+                Err(RecvError::Closed) => return None,
+            };
+
+            Some((Ok(change.into_event()), receiver))
+        }
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
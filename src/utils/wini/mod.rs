@@ -1,6 +1,12 @@
+pub mod admin;
 pub mod buffer;
 pub mod cache;
 pub mod cron;
 pub mod file;
 pub mod handling_file;
+pub mod live_reload;
+pub mod panic;
 pub mod path;
+pub mod security;
+pub mod session;
+pub mod status_pages;
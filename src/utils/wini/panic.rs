@@ -0,0 +1,82 @@
+use {
+    crate::shared::wini::err::{Backtrace, ServerError, ServerErrorKind, Trace},
+    axum::{
+        extract::{Request, State},
+        middleware::Next,
+        response::{IntoResponse, Response},
+    },
+    futures_util::FutureExt,
+    std::{
+        any::Any,
+        cell::RefCell,
+        panic::{AssertUnwindSafe, PanicHookInfo},
+        sync::Once,
+    },
+};
+
+
+thread_local! {
+    /// The location of the last panic of the thread, set by the panic hook
+    static PANIC_LOCATION: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+static SET_HOOK: Once = Once::new();
+
+/// Keep the location of the panics, since it isn't part of the payload caught by
+/// `catch_unwind`. The previous hook is still called.
+fn set_hook() {
+    SET_HOOK.call_once(|| {
+        let previous_hook = std::panic::take_hook();
+
+        std::panic::set_hook(Box::new(move |info: &PanicHookInfo| {
+            PANIC_LOCATION.set(info.location().map(ToString::to_string));
+            previous_hook(info);
+        }));
+    });
+}
+
+/// The message of a panic
+fn message_of(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("Box<dyn Any>")
+}
+
+
+/// Run `future`, and convert its panic into a [`ServerErrorKind::Panic`], with its location
+pub async fn catch<F: Future>(future: F) -> Result<F::Output, ServerErrorKind> {
+    set_hook();
+
+    let payload = match AssertUnwindSafe(future).catch_unwind().await {
+        Ok(output) => return Ok(output),
+        Err(payload) => payload,
+    };
+
+    // The future is polled by the thread on which it panicked
+    let message = match PANIC_LOCATION.take() {
+        Some(location) => format!("{} at {location}", message_of(payload.as_ref())),
+        None => message_of(payload.as_ref()).to_owned(),
+    };
+
+    Err(ServerErrorKind::Panic(message))
+}
+
+/// Convert the panics of the handler of a page into a [`ServerError`], which is rendered by the
+/// layouts with its [`Backtrace`]. `trace` is the page.
+///
+/// It is used by the router of the pages, generated by `build.rs`, around each page and action:
+/// the panics of the layouts and of the middleware aren't caught.
+pub async fn catch_panic(State(trace): State<Trace>, req: Request, next: Next) -> Response {
+    let mut err = match catch(next.run(req)).await {
+        Ok(resp) => return resp,
+        Err(kind) => ServerError::from(kind),
+    };
+    err.add_trace(trace);
+
+    let backtrace = Backtrace::from(err);
+    let mut resp = (&backtrace).into_response();
+    resp.extensions_mut().insert(backtrace);
+    resp
+}
@@ -1,3 +1,5 @@
+use std::fmt::{Display, Write};
+
 /// A macro to concatenate multiple path segments into a single `PathBuf`.
 ///
 /// This macro allows you to easily combine an arbitrary number of path segments
@@ -39,3 +41,28 @@ macro_rules! concat_paths {
         }
     };
 }
+
+
+/// Percent-encode a value so that it can be used as a segment of a path.
+///
+/// With `keep_slashes`, `/` is not encoded, so that the value can span multiple segments (for
+/// `{*wildcard}` parameters).
+///
+/// This is used by `url_for!`.
+pub fn encode_path_segment<V: Display>(value: V, keep_slashes: bool) -> String {
+    let value = value.to_string();
+    let mut encoded = String::with_capacity(value.len());
+
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() ||
+            matches!(byte, b'-' | b'.' | b'_' | b'~') ||
+            (keep_slashes && byte == b'/')
+        {
+            encoded.push(char::from(byte));
+        } else {
+            let _ = write!(encoded, "%{byte:02X}");
+        }
+    }
+
+    encoded
+}
@@ -0,0 +1,37 @@
+use {
+    crate::shared::wini::{config::SERVER_CONFIG, err::ServerResult},
+    axum::{extract::Request, http::HeaderValue, middleware::Next, response::Response},
+    base64::{Engine, engine::general_purpose::STANDARD},
+};
+
+/// The nonce of the current request, allowing the `<script>` and `<link>` tags that carry it in
+/// the Content-Security-Policy
+#[derive(Debug, Clone)]
+pub struct CspNonce(pub String);
+
+impl CspNonce {
+    fn generate() -> Self {
+        Self(STANDARD.encode(rand::random::<[u8; 16]>()))
+    }
+}
+
+/// Add the security headers specified in `./wini.toml`, and make a new nonce available to the
+/// rest of the request
+pub async fn security_headers(mut req: Request, next: Next) -> ServerResult<Response> {
+    let nonce = CspNonce::generate();
+    req.extensions_mut().insert(nonce.clone());
+
+    let mut resp = next.run(req).await;
+    let headers = resp.headers_mut();
+
+    for (name, value) in SERVER_CONFIG.security.headers(&nonce.0) {
+        // A handler can choose its own policy
+        if let Some(value) = value &&
+            !headers.contains_key(&name)
+        {
+            headers.insert(name, HeaderValue::from_str(&value)?);
+        }
+    }
+
+    Ok(resp)
+}
@@ -0,0 +1,113 @@
+use {
+    crate::shared::wini::{
+        config::SERVER_CONFIG,
+        err::ServerResult,
+        session::{CSRF_TOKEN_KEY, Session},
+    },
+    axum::{
+        body::Body,
+        extract::Request,
+        http::{HeaderMap, HeaderValue, Method},
+        middleware::Next,
+        response::Response,
+    },
+    hyper::{
+        StatusCode,
+        header::{CONTENT_TYPE, COOKIE, SET_COOKIE},
+    },
+    subtle::ConstantTimeEq,
+};
+
+/// The header in which the CSRF token can be sent, instead of the `_csrf` field of a form
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
+/// The maximum size of a form read to find its CSRF token
+const MAX_FORM_SIZE: usize = 2 * 1024 * 1024;
+
+/// Load the session of the request, make it available with the [`Session`] extractor and save it
+/// once the response is computed
+pub async fn session_layer(mut req: Request, next: Next) -> ServerResult<Response> {
+    let cookie = cookie_value(req.headers(), &SERVER_CONFIG.session.cookie_name);
+    let session = Session::load(cookie).await;
+    req.extensions_mut().insert(session.clone());
+
+    let mut resp = next.run(req).await;
+
+    if let Some(set_cookie) = session.save().await {
+        resp.headers_mut()
+            .append(SET_COOKIE, HeaderValue::from_str(&set_cookie)?);
+    }
+
+    Ok(resp)
+}
+
+/// Reject the requests that aren't `GET`, `HEAD`, `OPTIONS` or `TRACE` and that don't send the
+/// CSRF token of their session, either in the `X-CSRF-Token` header or in the `_csrf` field of an
+/// URL-encoded form.
+///
+/// This should be used inside of [`session_layer`].
+pub async fn csrf_protection(req: Request, next: Next) -> ServerResult<Response> {
+    if matches!(
+        *req.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+    ) {
+        return Ok(next.run(req).await);
+    }
+
+    let Some(session) = req.extensions().get::<Session>().cloned() else {
+        log::error!("The CSRF protection is used, but the session layer isn't.");
+        return Err(StatusCode::INTERNAL_SERVER_ERROR.into());
+    };
+
+    let (parts, body) = req.into_parts();
+
+    let from_header = parts
+        .headers
+        .get(CSRF_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned);
+
+    let is_form = parts
+        .headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("application/x-www-form-urlencoded"));
+
+    let (token, body) = match from_header {
+        Some(token) => (Some(token), body),
+        None if is_form => {
+            let bytes = axum::body::to_bytes(body, MAX_FORM_SIZE)
+                .await
+                .map_err(|_| StatusCode::PAYLOAD_TOO_LARGE)?;
+            let token = form_urlencoded::parse(&bytes)
+                .find(|(key, _)| key == CSRF_TOKEN_KEY)
+                .map(|(_, value)| value.into_owned());
+            (token, Body::from(bytes))
+        },
+        None => (None, body),
+    };
+
+    let is_valid = match (token, session.existing_csrf_token()) {
+        (Some(token), Some(expected)) => bool::from(token.as_bytes().ct_eq(expected.as_bytes())),
+        _ => false,
+    };
+
+    if !is_valid {
+        return Err(StatusCode::FORBIDDEN.into());
+    }
+
+    Ok(next.run(Request::from_parts(parts, body)).await)
+}
+
+/// The value of a cookie in the headers of a request
+fn cookie_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|cookies| cookies.split(';'))
+        .find_map(|cookie| {
+            let (key, value) = cookie.trim().split_once('=')?;
+            (key == name).then_some(value)
+        })
+}
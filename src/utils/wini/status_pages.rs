@@ -0,0 +1,88 @@
+//! The pages of the error status codes, such as `404 Not Found`, rendered inside of the layouts.
+
+use {
+    crate::shared::wini::{layer::Files, linked_file::LinkedFile},
+    axum::{
+        body::HttpBody,
+        extract::Request,
+        middleware::Next,
+        response::{Html, IntoResponse, Response},
+    },
+    hyper::{StatusCode, header::CONTENT_LENGTH},
+    maud::Markup,
+    std::{collections::HashMap, pin::Pin, sync::OnceLock},
+};
+
+
+/// The content of the page of a status code, usually a `#[component]`
+pub type StatusPage = fn() -> Pin<Box<dyn Future<Output = Markup> + Send>>;
+
+static STATUS_PAGES: OnceLock<HashMap<StatusCode, StatusPage>> = OnceLock::new();
+
+/// Set the pages of the status codes. Can only be done once: the next calls are ignored.
+///
+/// # Example
+///
+/// ```ignore
+/// status_pages::set_status_pages([(
+///     StatusCode::NOT_FOUND,
+///     (|| Box::pin(notfound::render())) as StatusPage,
+/// )]);
+/// ```
+pub fn set_status_pages<I: IntoIterator<Item = (StatusCode, StatusPage)>>(pages: I) {
+    if STATUS_PAGES.set(pages.into_iter().collect()).is_err() {
+        log::warn!("The status pages are already set.");
+    }
+}
+
+
+/// Replace the responses that have the status of a status page and an empty body, such as
+/// `StatusCode::NOT_FOUND.into_response()`, by the page of their status. Their status and headers
+/// are kept.
+///
+/// This should be used inside of the layouts.
+pub async fn status_pages(req: Request, next: Next) -> Response {
+    let resp = next.run(req).await;
+
+    if resp.body().size_hint().exact() != Some(0) {
+        return resp;
+    }
+
+    let Some(page) = render(resp.status()).await else {
+        return resp;
+    };
+
+    let (mut parts, _) = resp.into_parts();
+    parts.headers.remove(CONTENT_LENGTH);
+    let (page_parts, body) = page.into_parts();
+    parts.headers.extend(page_parts.headers);
+    parts.extensions.extend(page_parts.extensions);
+
+    Response::from_parts(parts, body)
+}
+
+/// The fallback of the router: the page of `404 Not Found`
+pub async fn fallback() -> Response {
+    let mut resp = render(StatusCode::NOT_FOUND)
+        .await
+        .unwrap_or_else(|| ().into_response());
+    *resp.status_mut() = StatusCode::NOT_FOUND;
+    resp
+}
+
+/// The page of a status code, if there is one
+async fn render(status: StatusCode) -> Option<Response> {
+    let page = STATUS_PAGES.get()?.get(&status)?;
+    let html = page().await;
+
+    let mut resp = (status, Html(html.content.0)).into_response();
+
+    let files: &mut Files = resp.extensions_mut().get_or_insert_default();
+    files.extend(
+        html.linked_files
+            .iter()
+            .map(|file| LinkedFile::decode(file)),
+    );
+
+    Some(resp)
+}
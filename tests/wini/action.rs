@@ -0,0 +1,145 @@
+use {
+    axum::{
+        body::Body,
+        http::{Method, Request, StatusCode, header},
+        response::{Html, IntoResponse, Response},
+    },
+    serde::Deserialize,
+    wini_website::shared::wini::action::{
+        ActionFeedback,
+        ActionResponse,
+        FieldErrors,
+        Submission,
+        Validate,
+    },
+};
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Subscription {
+    email: String,
+    age: u8,
+}
+
+impl Validate for Subscription {
+    fn validate(&self) -> FieldErrors {
+        let mut errors = FieldErrors::default();
+        if !self.email.contains('@') {
+            errors.add("email", "This is not a valid email address.");
+        }
+        errors
+    }
+}
+
+fn submit(body: &'static str) -> Request<Body> {
+    Request::post("/subscribe")
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(Body::from(body))
+        .unwrap()
+}
+
+/// The page of the form, rendered again when it's invalid
+async fn page(method: Method, feedback: ActionFeedback) -> impl IntoResponse {
+    Html(format!(
+        "{method} {} {}",
+        feedback.value("email"),
+        feedback.error("email").unwrap_or("-")
+    ))
+}
+
+/// What the generated handler of an `#[action]` does, with its `State`
+async fn action(req: Request<Body>) -> Response {
+    let mut submission = Submission::<Subscription>::from_request(req).await;
+
+    let response = match submission.validated() {
+        Ok(form) => ActionResponse::Redirect(format!("/thanks/{}", form.age)),
+        Err(errors) => ActionResponse::Invalid(errors),
+    };
+
+    submission.respond(response, page, ()).await
+}
+
+async fn body_of(resp: Response) -> String {
+    String::from_utf8(
+        axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap()
+            .to_vec(),
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn forms_are_deserialized() {
+    let submission = Submission::<Subscription>::from_request(submit("email=a%40b.c&age=30")).await;
+
+    assert_eq!(
+        submission.form.unwrap(),
+        Subscription {
+            email: "a@b.c".to_owned(),
+            age: 30,
+        }
+    );
+    assert_eq!(
+        submission.values.get("email").map(String::as_str),
+        Some("a@b.c")
+    );
+    assert_eq!(submission.request.uri(), "/subscribe");
+}
+
+#[tokio::test]
+async fn forms_that_cant_be_deserialized_are_invalid() {
+    let mut submission =
+        Submission::<Subscription>::from_request(submit("email=a%40b.c&age=old")).await;
+
+    let errors = submission.validated().unwrap_err();
+    assert!(errors.get(FieldErrors::FORM).is_some());
+    assert!(errors.get("email").is_none());
+    assert_eq!(
+        submission.values.get("age").map(String::as_str),
+        Some("old")
+    );
+}
+
+#[tokio::test]
+async fn invalid_forms_are_rejected_by_their_validation() {
+    let mut submission =
+        Submission::<Subscription>::from_request(submit("email=nope&age=30")).await;
+
+    let errors = submission.validated().unwrap_err();
+    assert_eq!(
+        errors.get("email"),
+        Some("This is not a valid email address.")
+    );
+}
+
+#[tokio::test]
+async fn the_page_is_rendered_again_when_the_form_is_invalid() {
+    let resp = action(submit("email=nope&age=30")).await;
+
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    // With the submitted values and the errors, as a `GET`
+    assert_eq!(
+        body_of(resp).await,
+        "GET nope This is not a valid email address."
+    );
+}
+
+#[tokio::test]
+async fn valid_forms_are_redirected() {
+    let resp = action(submit("email=a%40b.c&age=30")).await;
+
+    assert_eq!(resp.status(), StatusCode::SEE_OTHER);
+    assert_eq!(resp.headers()[header::LOCATION], "/thanks/30");
+}
+
+#[tokio::test]
+async fn htmx_requests_are_redirected_with_htmx() {
+    let mut req = submit("email=a%40b.c&age=30");
+    req.headers_mut()
+        .insert("hx-request", "true".parse().unwrap());
+
+    let resp = action(req).await;
+
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers()["hx-redirect"], "/thanks/30");
+}
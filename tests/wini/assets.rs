@@ -0,0 +1,112 @@
+use {
+    axum::{
+        Router,
+        body::Body,
+        http::{Request, StatusCode, header},
+        middleware,
+    },
+    tower::ServiceExt,
+    wini_website::{
+        shared::wini::{
+            CSS_FILES,
+            assets::{HASH_LEN, MANIFEST, Manifest, fingerprint, hash},
+            cache::CacheCategory,
+            config::SERVER_CONFIG,
+        },
+        utils::wini::handling_file::handle_file,
+    },
+};
+
+const CSS: &[u8] = b"body { color: red; }";
+
+#[test]
+fn the_hash_is_before_the_extension() {
+    let hash = hash(CSS, HASH_LEN);
+    assert_eq!(hash.len(), HASH_LEN * 2);
+    assert!(hash.bytes().all(|byte| byte.is_ascii_hexdigit()));
+
+    assert_eq!(
+        fingerprint("/src/pages/doc/style.css", CSS),
+        format!("/src/pages/doc/style.{hash}.css")
+    );
+    assert_eq!(
+        fingerprint("/modules/x/x.min.js", CSS),
+        format!("/modules/x/x.min.{hash}.js")
+    );
+    assert_eq!(fingerprint("/LICENSE", CSS), format!("/LICENSE.{hash}"));
+}
+
+#[test]
+fn the_fingerprint_changes_with_the_content() {
+    assert_ne!(
+        fingerprint("/style.css", CSS),
+        fingerprint("/style.css", b"body { color: blue; }")
+    );
+}
+
+#[test]
+fn the_manifest_maps_both_ways() {
+    let mut manifest = Manifest::default();
+    manifest.insert("/src/style.css", CSS, "src/style.css");
+    let fingerprinted_url = fingerprint("/src/style.css", CSS);
+
+    assert_eq!(manifest.url("/src/style.css"), fingerprinted_url);
+    assert_eq!(manifest.file(&fingerprinted_url), Some("/src/style.css"));
+    assert!(manifest.validators("/src/style.css").is_some());
+    assert!(manifest.integrity("/src/style.css").is_some());
+
+    // The other files aren't fingerprinted
+    assert_eq!(manifest.url("/main.css"), "/main.css");
+    assert_eq!(manifest.file("/src/style.css"), None);
+}
+
+/// A CSS file of `src/`, removed when it's dropped
+struct CssFile(&'static str);
+
+impl CssFile {
+    fn new(path: &'static str) -> Self {
+        std::fs::write(path, CSS).unwrap();
+        CSS_FILES.reload();
+        MANIFEST.reload();
+        Self(path)
+    }
+}
+
+impl Drop for CssFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(self.0);
+    }
+}
+
+#[tokio::test]
+async fn fingerprinted_files_are_cached_forever() {
+    let _file = CssFile::new("src/wini-test-fingerprinted.css");
+    let url = "/src/wini-test-fingerprinted.css".to_owned();
+    let fingerprinted_url = MANIFEST.load().url(&url).to_owned();
+    assert_ne!(fingerprinted_url, url);
+
+    let app = Router::new().layer(middleware::from_fn(handle_file));
+    let get = |uri: String| {
+        let app = app.clone();
+        async move {
+            app.oneshot(Request::get(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap()
+        }
+    };
+
+    let resp = get(fingerprinted_url).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.headers()[header::CACHE_CONTROL],
+        SERVER_CONFIG
+            .cache
+            .get_or_panic(CacheCategory::Fingerprinted)
+    );
+
+    let resp = get(url).await;
+    assert_eq!(
+        resp.headers()[header::CACHE_CONTROL],
+        SERVER_CONFIG.cache.get_or_panic(CacheCategory::Css)
+    );
+}
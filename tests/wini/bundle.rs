@@ -0,0 +1,170 @@
+use {
+    std::sync::Arc,
+    wini_website::shared::wini::bundle::{Bundles, bundled_css, can_bundle_css},
+};
+
+
+const URL: &str = "/src/pages/doc/style.css";
+
+#[test]
+fn css_is_minified() {
+    let css = r#"
+/* The links */
+a :hover,
+a > b {
+    color : red;
+    content: "  a  /* b */  ";
+    margin: 0 auto;
+}
+
+@media (max-width: 600px) {
+    a { font-family: 'Fira Code', monospace; }
+}
+"#;
+
+    assert_eq!(
+        bundled_css(URL, css, true).unwrap(),
+        r#"a :hover,a>b{color:red;content:"  a  /* b */  ";margin:0 auto}@media (width<=600px){a{font-family:Fira Code,monospace}}"#
+    );
+}
+
+#[test]
+fn minified_css_keeps_its_meaning() {
+    // The spaces around the operators of `calc()` are needed
+    assert_eq!(
+        bundled_css(URL, "a { width: calc(100% - 2 * var(--gap)); }", true).unwrap(),
+        "a{width:calc(100% - 2 * var(--gap))}"
+    );
+
+    // The escaped quotes don't end the strings
+    assert_eq!(
+        bundled_css(URL, r#"a::before { content: "\"/* a */\" }"; }"#, true).unwrap(),
+        r#"a:before{content:"\"/* a */\" }"}"#
+    );
+
+    // Empty comments, and comments with stars or in the middle of a value
+    assert_eq!(
+        bundled_css(
+            URL,
+            "/**/ a { /* ** / */ margin: 0/**/auto; } /* a */ /***/",
+            true
+        )
+        .unwrap(),
+        "a{margin:0 auto}"
+    );
+
+    // Not minified outside of production, but still valid
+    assert_eq!(
+        bundled_css(URL, "a{color:red}", false).unwrap(),
+        "a {\n  color: red;\n}\n"
+    );
+}
+
+#[test]
+fn relative_urls_are_made_absolute() {
+    let css = r#"
+a { background: url(img/a.png); }
+b { background: url("../../fonts/b.woff2?v=1#x"); }
+c { background: url(/c.png), url(https://example.com/c.png); }
+d { background: url(data:image/png;base64,AAAA); filter: url(#shadow); }
+"#;
+
+    let bundled = bundled_css(URL, css, true).unwrap();
+    assert!(bundled.contains("url(/src/pages/doc/img/a.png)"));
+    assert!(bundled.contains("url(/src/fonts/b.woff2?v=1#x)"));
+    assert!(bundled.contains("url(/c.png),url(https://example.com/c.png)"));
+    assert!(bundled.contains("url(data:image/png;base64,AAAA)"));
+    assert!(bundled.contains("url(#shadow)"));
+}
+
+#[test]
+fn css_that_must_be_first_is_not_bundled() {
+    assert!(can_bundle_css(URL, "@charset \"utf-8\"; a { color: red; }"));
+    assert!(!can_bundle_css(
+        URL,
+        "@import url(./other.css); a { color: red; }"
+    ));
+    assert!(!can_bundle_css(
+        URL,
+        "@namespace svg url(http://www.w3.org/2000/svg);"
+    ));
+    // Nor the style sheets that can't be parsed
+    assert!(!can_bundle_css(URL, "a {{ }"));
+}
+
+fn files() -> [(String, String); 3] {
+    [
+        ("/src/a.css", "/src/a.1a2b3c4d.css"),
+        ("/src/b.css", "/src/b.5e6f7a8b.css"),
+        ("/src/a.js", "/src/a.9c8d7e6f.js"),
+    ]
+    .map(|(url, fingerprinted_url)| (url.to_owned(), fingerprinted_url.to_owned()))
+}
+
+#[test]
+fn bundles_urls_are_derived_from_the_files() {
+    let bundles = Bundles::new(files());
+    let styles = ["/src/a.css".to_owned(), "/src/b.css".to_owned()];
+
+    // Another server with the same files has the same URL
+    let url = bundles.url(&styles).unwrap();
+    assert!(url.starts_with("/__wini/bundle/") && url.ends_with(".css"));
+    assert_eq!(Bundles::new(files()).url(&styles), Some(url.clone()));
+
+    // Once a file changes, the bundle has a new URL
+    let mut changed = files();
+    if let Some((_, fingerprinted_url)) = changed.get_mut(1) {
+        "/src/b.0f0f0f0f.css".clone_into(fingerprinted_url);
+    }
+    assert_ne!(Bundles::new(changed).url(&styles), Some(url.clone()));
+
+    // The files must all be known
+    assert_eq!(bundles.url(&["/src/c.css".to_owned()]), None);
+}
+
+#[test]
+fn bundles_urls_have_sorted_and_unique_ids() {
+    let bundles = Bundles::new(files());
+    let ids = |url: &str| {
+        let (_, ids) = url.rsplit_once('/').unwrap();
+        ids.trim_end_matches(".css").to_owned()
+    };
+
+    let url = bundles
+        .url(&["/src/b.css".to_owned(), "/src/a.css".to_owned()])
+        .unwrap();
+    let reversed_url = bundles
+        .url(&["/src/a.css".to_owned(), "/src/b.css".to_owned()])
+        .unwrap();
+
+    // The order of the files is in the version
+    assert_ne!(url, reversed_url);
+    assert_eq!(ids(&url), ids(&reversed_url));
+    let ids = ids(&url);
+    let ids = ids.split('-').collect::<Vec<_>>();
+    assert!(ids.is_sorted());
+
+    assert_eq!(
+        bundles.url(&["/src/a.css".to_owned(), "/src/a.css".to_owned()]),
+        None
+    );
+}
+
+#[tokio::test]
+async fn only_the_bundles_of_the_pages_are_served() {
+    let bundles = Arc::new(Bundles::new([(
+        "/main.css".to_owned(),
+        "/main.css".to_owned(),
+    )]));
+    let files = vec!["/main.css".to_owned()];
+    let url = bundles.url(&files).unwrap();
+
+    // Not until a page links it
+    assert!(bundles.built(&url).is_none());
+
+    assert_eq!(
+        Arc::clone(&bundles).register(files).await,
+        Some(url.clone())
+    );
+    assert!(bundles.built(&url).is_some());
+}
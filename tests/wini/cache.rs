@@ -0,0 +1,172 @@
+use {
+    axum::{
+        Router,
+        body::Body,
+        http::{HeaderValue, Request, StatusCode, header},
+        middleware,
+        response::{Html, IntoResponse, Response},
+        routing::get,
+    },
+    tower::ServiceExt,
+    wini_website::{
+        server,
+        shared::wini::health,
+        template::template,
+        utils::wini::cache::conditional_requests,
+    },
+};
+
+fn app(cache_rule: &'static str) -> Router {
+    Router::new()
+        .route(
+            "/file",
+            get(move || {
+                async move {
+                    (
+                        [
+                            (header::ETAG, "\"abc\""),
+                            (header::LAST_MODIFIED, "Wed, 21 Oct 2015 07:28:00 GMT"),
+                            (header::CACHE_CONTROL, cache_rule),
+                        ],
+                        "body { color: red; }",
+                    )
+                        .into_response()
+                }
+            }),
+        )
+        .route("/page", get(|| async { Html("<p>Hello</p>") }))
+        .layer(middleware::from_fn(template))
+        .layer(middleware::from_fn(conditional_requests))
+}
+
+async fn status_of(app: Router, req: Request<Body>) -> StatusCode {
+    app.oneshot(req).await.unwrap().status()
+}
+
+#[tokio::test]
+async fn matching_etags_are_not_modified() {
+    let req = Request::get("/file")
+        .header(header::IF_NONE_MATCH, "\"xyz\", W/\"abc\"")
+        .body(Body::empty())
+        .unwrap();
+    let resp = app("no-cache").oneshot(req).await.unwrap();
+
+    assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(resp.headers()[header::ETAG], "\"abc\"");
+    assert!(
+        axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    let req = Request::get("/file")
+        .header(header::IF_NONE_MATCH, "\"xyz\"")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(status_of(app("no-cache"), req).await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn if_modified_since_is_used_without_etags() {
+    let req = Request::get("/file")
+        .header(header::IF_MODIFIED_SINCE, "Thu, 22 Oct 2015 07:28:00 GMT")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(
+        status_of(app("no-cache"), req).await,
+        StatusCode::NOT_MODIFIED
+    );
+
+    let req = Request::get("/file")
+        .header(header::IF_MODIFIED_SINCE, "Tue, 20 Oct 2015 07:28:00 GMT")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(status_of(app("no-cache"), req).await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn no_store_responses_have_no_validators() {
+    let req = Request::get("/file")
+        .header(header::IF_NONE_MATCH, "\"abc\"")
+        .body(Body::empty())
+        .unwrap();
+    let resp = app("no-store").oneshot(req).await.unwrap();
+
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(!resp.headers().contains_key(header::ETAG));
+    assert!(!resp.headers().contains_key(header::LAST_MODIFIED));
+}
+
+#[tokio::test]
+async fn pages_with_a_nonce_are_not_revalidated() {
+    // The policy of `wini.toml` uses `{nonce}`
+    let resp = app("no-cache")
+        .oneshot(Request::get("/page").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert!(!resp.headers().contains_key(header::ETAG));
+
+    let req = Request::get("/page")
+        .header(header::IF_NONE_MATCH, "*")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(status_of(app("no-cache"), req).await, StatusCode::OK);
+}
+
+/// The nonce of the policy of a response, and the one of the first script of its page
+async fn nonces_of(resp: Response) -> (String, String) {
+    let policy = resp.headers()[header::CONTENT_SECURITY_POLICY]
+        .to_str()
+        .unwrap()
+        .to_owned();
+    let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body = String::from_utf8(body.to_vec()).unwrap();
+
+    let after = |text: &str, prefix: &str, end: char| {
+        let (_, rest) = text.split_once(prefix).unwrap();
+        rest.split(end).next().unwrap().to_owned()
+    };
+
+    (
+        after(&policy, "'nonce-", '\''),
+        after(&body, "<script nonce=\"", '"'),
+    )
+}
+
+#[tokio::test]
+async fn revalidated_pages_carry_the_nonce_of_their_policy() {
+    health::mark_as_ready();
+    let app = server::router();
+
+    let resp = app
+        .clone()
+        .oneshot(
+            Request::get("/doc/introduction")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let etag = resp.headers().get(header::ETAG).cloned();
+    let (policy_nonce, page_nonce) = nonces_of(resp).await;
+    assert_eq!(policy_nonce, page_nonce);
+
+    let req = Request::get("/doc/introduction")
+        .header(
+            header::IF_NONE_MATCH,
+            etag.unwrap_or(HeaderValue::from_static("*")),
+        )
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.oneshot(req).await.unwrap();
+
+    // The page is sent again, with the nonce of the new policy
+    assert_eq!(resp.status(), StatusCode::OK);
+    let (new_policy_nonce, new_page_nonce) = nonces_of(resp).await;
+    assert_eq!(new_policy_nonce, new_page_nonce);
+    assert_ne!(new_policy_nonce, policy_nonce);
+}
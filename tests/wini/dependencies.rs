@@ -0,0 +1,73 @@
+use {
+    std::collections::HashMap,
+    wini_website::shared::wini::dependencies::{Dependency, DependencyGraph},
+};
+
+
+fn graph(scripts: &[(&str, &str)]) -> DependencyGraph {
+    let sources = scripts
+        .iter()
+        .map(|(url, source)| ((*url).to_owned(), (*source).to_owned()))
+        .collect::<HashMap<_, _>>();
+
+    DependencyGraph::new(sources.keys().cloned(), |url| sources.get(url).cloned())
+}
+
+fn script(url: &str) -> Dependency {
+    Dependency::Script(url.to_owned())
+}
+
+#[test]
+fn scripts_are_after_their_dependencies() {
+    let graph = graph(&[
+        ("/src/pages/page.js", "import './helper'; import 'package';"),
+        ("/src/pages/helper.js", "import { f } from '../utils/f.ts';"),
+        ("/src/utils/f.js", "export const f = 1;"),
+        ("/src/other.js", "import '/src/utils/f.js';"),
+    ]);
+
+    let expected = [
+        script("/src/utils/f.js"),
+        script("/src/pages/helper.js"),
+        Dependency::Package("package".to_owned()),
+        script("/src/pages/page.js"),
+        script("/src/other.js"),
+    ];
+
+    // The same order each time
+    for _ in 0..10 {
+        assert_eq!(
+            graph.order(["/src/pages/page.js", "/src/other.js"]),
+            expected
+        );
+    }
+    assert!(graph.cycles().is_empty());
+}
+
+#[test]
+fn circular_imports_are_reported() {
+    let graph = graph(&[
+        ("/src/a.js", "import './b';"),
+        ("/src/b.js", "import './c';"),
+        ("/src/c.js", "import './a';"),
+        ("/src/d.js", "import './d';"),
+    ]);
+
+    assert_eq!(
+        graph.cycles(),
+        [
+            vec!["/src/a.js", "/src/b.js", "/src/c.js", "/src/a.js"],
+            vec!["/src/d.js", "/src/d.js"],
+        ]
+    );
+
+    // Each script is still linked once
+    assert_eq!(
+        graph.order(["/src/b.js"]),
+        [
+            script("/src/a.js"),
+            script("/src/c.js"),
+            script("/src/b.js")
+        ]
+    );
+}
@@ -0,0 +1,75 @@
+use {
+    axum::{http::HeaderMap, response::IntoResponse},
+    maud::html,
+    serde::Serialize,
+    wini_website::shared::wini::{
+        alpine::XData,
+        htmx::{HxRequest, HxResponse, Swap},
+    },
+};
+
+#[test]
+fn request_headers_are_parsed() {
+    let mut headers = HeaderMap::new();
+    headers.insert("hx-request", "true".parse().unwrap());
+    headers.insert("hx-target", "content".parse().unwrap());
+    headers.insert("hx-trigger", "next".parse().unwrap());
+    headers.insert(
+        "hx-current-url",
+        "http://localhost/doc/pages".parse().unwrap(),
+    );
+
+    let hx = HxRequest::from_headers(&headers);
+
+    assert!(hx.is_htmx);
+    assert_eq!(hx.target.as_deref(), Some("content"));
+    assert_eq!(hx.trigger.as_deref(), Some("next"));
+    assert_eq!(hx.current_url.as_ref().unwrap().path(), "/doc/pages");
+    assert!(hx.is_fragment());
+
+    headers.insert("hx-history-restore-request", "true".parse().unwrap());
+    assert!(!HxRequest::from_headers(&headers).is_fragment());
+
+    assert!(!HxRequest::from_headers(&HeaderMap::new()).is_htmx);
+}
+
+#[test]
+fn response_headers_are_set() {
+    let resp = HxResponse::default()
+        .push_url("/doc/pages")
+        .trigger("saved")
+        .trigger("closed")
+        .reswap(Swap::OuterHtml)
+        .into_response();
+
+    assert_eq!(resp.headers()["hx-push-url"], "/doc/pages");
+    assert_eq!(resp.headers()["hx-trigger"], "saved, closed");
+    assert_eq!(resp.headers()["hx-reswap"], "outerHTML");
+    assert!(!resp.headers().contains_key("hx-redirect"));
+}
+
+#[test]
+fn invalid_response_headers_are_errors() {
+    let resp = HxResponse::default().redirect("/\n").into_response();
+
+    assert!(resp.status().is_server_error());
+}
+
+#[test]
+fn alpine_data_is_escaped() {
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Data<'l> {
+        is_open: bool,
+        page: &'l str,
+    }
+
+    let markup = html! {
+        div x-data=(XData(Data { is_open: false, page: "'\"><script>" })) {}
+    };
+
+    assert_eq!(
+        markup.into_string(),
+        r#"<div x-data="{&quot;isOpen&quot;:false,&quot;page&quot;:&quot;'\&quot;&gt;&lt;script&gt;&quot;}"></div>"#
+    );
+}
@@ -0,0 +1,141 @@
+use {
+    std::{collections::HashMap, path::Path},
+    wini_website::shared::wini::{
+        assets::Manifest,
+        import_map::ImportMap,
+        linked_file::LinkedFile,
+        tsconfig::TsConfigPaths,
+    },
+};
+
+/// A `tsconfig.json` in a directory of the project, so that its paths are relative to the root
+fn tsconfig_paths(name: &str, tsconfig: &str) -> TsConfigPaths {
+    let directory = Path::new("target").join(format!("wini-import-map-{name}"));
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(directory.join("tsconfig.json"), tsconfig).unwrap();
+
+    TsConfigPaths::load(&directory.join("tsconfig.json"))
+}
+
+fn import_map(
+    scripts: &[&str],
+    tsconfig_paths: &TsConfigPaths,
+    packages_files: &HashMap<String, Vec<LinkedFile>>,
+) -> ImportMap {
+    let scripts = scripts
+        .iter()
+        .map(|script| (*script).to_owned())
+        .collect::<Vec<_>>();
+
+    ImportMap::new(
+        &Manifest::default(),
+        &scripts,
+        tsconfig_paths,
+        packages_files,
+    )
+}
+
+#[test]
+fn scripts_are_mapped_with_and_without_their_extension() {
+    let import_map = import_map(
+        &["/src/utils/date.js"],
+        &TsConfigPaths::default(),
+        &HashMap::new(),
+    );
+
+    for specifier in [
+        "/src/utils/date.js",
+        "/src/utils/date",
+        "/src/utils/date.ts",
+    ] {
+        assert_eq!(import_map.get(specifier), Some("/src/utils/date.js"));
+    }
+    assert_eq!(import_map.get("/src/utils/time"), None);
+}
+
+#[test]
+fn paths_of_tsconfig_are_mapped() {
+    let tsconfig_paths = tsconfig_paths(
+        "paths",
+        r#"{ "compilerOptions": { "baseUrl": "../..", "paths": { "~/*": ["./src/*"] } } }"#,
+    );
+    let import_map = import_map(
+        &[
+            "/src/utils/date.js",
+            "/src/components/nav/index.js",
+        ],
+        &tsconfig_paths,
+        &HashMap::new(),
+    );
+
+    for specifier in [
+        "~/utils/date",
+        "~/utils/date.js",
+        "~/utils/date.ts",
+        "src/utils/date",
+    ] {
+        assert_eq!(import_map.get(specifier), Some("/src/utils/date.js"));
+    }
+    // A directory is imported with its `index`
+    assert_eq!(
+        import_map.get("~/components/nav"),
+        Some("/src/components/nav/index.js")
+    );
+    // And the other files of the directory
+    assert_eq!(import_map.get("~/"), Some("/src/"));
+}
+
+#[test]
+fn packages_are_mapped_to_their_script() {
+    let packages_files = HashMap::from([(
+        "htmx.org".to_owned(),
+        vec![
+            LinkedFile::new("/modules/htmx.org/htmx.css"),
+            LinkedFile {
+                integrity: Some("sha384-abc".to_owned()),
+                ..LinkedFile::new("/modules/htmx.org/htmx.min.js")
+            },
+        ],
+    )]);
+    let import_map = import_map(&[], &TsConfigPaths::default(), &packages_files);
+
+    assert_eq!(
+        import_map.get("htmx.org"),
+        Some("/modules/htmx.org/htmx.min.js")
+    );
+    assert_eq!(
+        import_map.integrity("/modules/htmx.org/htmx.min.js"),
+        Some("sha384-abc")
+    );
+
+    let json = serde_json::from_str::<serde_json::Value>(import_map.json()).unwrap();
+    assert_eq!(
+        json.get("imports")
+            .and_then(|imports| imports.get("htmx.org")),
+        Some(&"/modules/htmx.org/htmx.min.js".into())
+    );
+    assert_eq!(
+        json.get("integrity")
+            .and_then(|integrity| integrity.get("/modules/htmx.org/htmx.min.js")),
+        Some(&"sha384-abc".into())
+    );
+}
+
+#[test]
+fn the_json_cant_close_its_script() {
+    let import_map = import_map(
+        &["/src/</script>.js"],
+        &TsConfigPaths::default(),
+        &HashMap::new(),
+    );
+
+    assert!(!import_map.json().contains("</"));
+    assert!(import_map.json().contains(r"<\/script>"));
+
+    let json = serde_json::from_str::<serde_json::Value>(import_map.json()).unwrap();
+    assert_eq!(
+        json.get("imports")
+            .and_then(|imports| imports.get("/src/</script>")),
+        Some(&"/src/</script>.js".into())
+    );
+}
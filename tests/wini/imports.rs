@@ -0,0 +1,75 @@
+use wini_website::shared::wini::imports::imports;
+
+#[test]
+fn static_imports_are_found() {
+    let source = r#"
+import "./side-effect";
+import a from "./a"
+import { b,
+    c as d,
+} from './b';
+import * as e from "./e";
+import f, { g } from "./f"
+import type { H } from "./types";
+import type from "./type";
+import json from "./data.json" with { type: "json" };
+import last from "./last""#;
+
+    assert_eq!(
+        imports(source),
+        [
+            "./side-effect",
+            "./a",
+            "./b",
+            "./e",
+            "./f",
+            "./type",
+            "./data.json",
+            "./last"
+        ]
+    );
+}
+
+#[test]
+fn reexports_dynamic_imports_and_requires_are_found() {
+    let source = r#"
+export * from "./all";
+export * as ns from "./namespace";
+export { a, b as c } from "./some";
+export type { T } from "./types";
+export { local };
+export const value = 1;
+
+const lazy = await import("./lazy");
+const { debug } = require('debug');
+const meta = import.meta.url;
+const computed = import(`./pages/${name}.js`);
+loader.import("./not-an-import");
+"#;
+
+    assert_eq!(
+        imports(source),
+        [
+            "./all",
+            "./namespace",
+            "./some",
+            "./lazy",
+            "debug"
+        ]
+    );
+}
+
+#[test]
+fn comments_strings_and_regexes_are_ignored() {
+    let source = r#"
+// import "./line-comment";
+/* import "./block-comment";
+   export * from "./block-comment"; */
+const string = 'import "./string"';
+const template = `import "./template" ${value + `nested ${"require('./nested')"}`}`;
+const regex = /import "\.\/regex"/g;
+const ratio = total / count; import "./after-division";
+"#;
+
+    assert_eq!(imports(source), ["./after-division"]);
+}
@@ -0,0 +1,44 @@
+use wini_website::shared::wini::integrity::{integrity, matches};
+
+const SCRIPT: &[u8] = b"alert('Hello, world.');";
+
+#[test]
+fn integrity_is_a_sha384_in_base64() {
+    assert_eq!(
+        integrity(SCRIPT),
+        "sha384-H8BRh8j48O9oYatfu5AZzq6A9RINhZO5H16dQZngK7T62em8MUt1FLm52t+eX6xO"
+    );
+}
+
+#[test]
+fn one_of_the_known_hashes_must_match() {
+    assert!(matches(&integrity(SCRIPT), SCRIPT));
+    assert!(!matches(&integrity(SCRIPT), b"alert('Bye');"));
+
+    // The hashes of unknown algorithms are ignored
+    assert!(matches(
+        &format!("md5-xxx {}?options", integrity(SCRIPT)),
+        SCRIPT
+    ));
+    assert!(!matches("md5-xxx", SCRIPT));
+    assert!(matches(
+        "sha256-qznLcsROx4GACP2dm0UCKCzCG+HiZ1guq6ZZDob/Tng=",
+        SCRIPT
+    ));
+}
+
+#[test]
+fn only_the_strongest_algorithm_is_checked() {
+    let sha256 = "sha256-qznLcsROx4GACP2dm0UCKCzCG+HiZ1guq6ZZDob/Tng=";
+
+    // Like the browsers, which would block it
+    assert!(!matches(&format!("{sha256} sha512-xxx"), SCRIPT));
+    assert!(matches(
+        &format!("sha256-xxx {}", integrity(SCRIPT)),
+        SCRIPT
+    ));
+    assert!(matches(
+        &format!("sha384-xxx {}", integrity(SCRIPT)),
+        SCRIPT
+    ));
+}
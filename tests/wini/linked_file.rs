@@ -0,0 +1,77 @@
+use wini_website::shared::wini::linked_file::{
+    CrossOrigin,
+    FileKind,
+    LinkedFile,
+    Loading,
+    Placement,
+};
+
+#[test]
+fn kind_is_guessed_from_the_extension() {
+    assert_eq!(
+        LinkedFile::new("src/style.css").kind(),
+        FileKind::Stylesheet
+    );
+    assert_eq!(
+        LinkedFile::new("/modules/x/x.min.js").kind(),
+        FileKind::Script
+    );
+    assert_eq!(LinkedFile::new("/fonts/inter.woff2").kind(), FileKind::Font);
+    assert_eq!(LinkedFile::new("/data.json").kind(), FileKind::Preload);
+
+    let module = LinkedFile {
+        kind: Some(FileKind::Module),
+        ..LinkedFile::new("/x.js")
+    };
+    assert_eq!(module.kind(), FileKind::Module);
+}
+
+#[test]
+fn urls_are_absolute() {
+    assert_eq!(LinkedFile::new("src/style.css").url, "/src/style.css");
+    assert_eq!(LinkedFile::new("/main.css").url, "/main.css");
+    assert_eq!(
+        LinkedFile::new("https://cdn.example.com/a.js").url,
+        "https://cdn.example.com/a.js"
+    );
+}
+
+#[test]
+fn pinned_integrity_is_checked_in_cors_mode() {
+    let file = LinkedFile {
+        integrity: Some("sha384-abc".to_owned()),
+        ..LinkedFile::new("https://cdn.example.com/a.js")
+    };
+    let html = file.to_html(None).into_string();
+    assert!(html.contains(r#"integrity="sha384-abc""#));
+    assert!(html.contains(r#"crossorigin="anonymous""#));
+
+    // Unless another mode is set
+    let file = LinkedFile {
+        crossorigin: Some(CrossOrigin::UseCredentials),
+        ..file
+    };
+    assert!(
+        file.to_html(None)
+            .into_string()
+            .contains(r#"crossorigin="use-credentials""#)
+    );
+}
+
+#[test]
+fn files_are_encoded_and_decoded() {
+    // Without options, a file is only its path
+    let file = LinkedFile::new("src/style.css");
+    assert!(!file.has_options());
+    assert_eq!(file.encode(), "/src/style.css");
+    assert_eq!(LinkedFile::decode("src/style.css"), file);
+
+    let file = LinkedFile {
+        loading: Loading::Async,
+        media: Some("print".to_owned()),
+        placement: Placement::Body,
+        ..LinkedFile::new("src/print.css")
+    };
+    assert!(file.has_options());
+    assert_eq!(LinkedFile::decode(&file.encode()), file);
+}
@@ -0,0 +1,75 @@
+use {
+    std::time::Duration,
+    wini_website::shared::wini::metrics::{CounterVec, HistogramVec},
+};
+
+#[test]
+fn counters_are_encoded_per_label_values() {
+    let counter = CounterVec::new("test_total", "Tests.", &["route", "status"]);
+    counter.inc(&["/b", "200"]);
+    counter.inc(&["/a", "404"]);
+    counter.inc(&["/b", "200"]);
+
+    let mut out = String::new();
+    counter.encode(&mut out);
+
+    assert_eq!(
+        out,
+        "# HELP test_total Tests.\n\
+         # TYPE test_total counter\n\
+         test_total{route=\"/a\",status=\"404\"} 1\n\
+         test_total{route=\"/b\",status=\"200\"} 2\n"
+    );
+}
+
+#[test]
+fn counters_without_labels_have_no_braces() {
+    let counter = CounterVec::new("runs_total", "Runs.", &[]);
+    counter.inc(&[]);
+
+    let mut out = String::new();
+    counter.encode(&mut out);
+
+    assert!(out.ends_with("\nruns_total 1\n"));
+}
+
+#[test]
+fn label_values_are_escaped() {
+    let counter = CounterVec::new("test_total", "Tests.", &["route"]);
+    counter.inc(&["/a\"b\\c\nd"]);
+
+    let mut out = String::new();
+    counter.encode(&mut out);
+
+    assert!(out.ends_with("test_total{route=\"/a\\\"b\\\\c\\nd\"} 1\n"));
+}
+
+#[test]
+fn histograms_have_cumulative_buckets() {
+    let histogram = HistogramVec::new("latency_seconds", "Latency.", &["route"]);
+    histogram.observe(&["/"], Duration::from_millis(20));
+    histogram.observe(&["/"], Duration::from_millis(300));
+    histogram.observe(&["/"], Duration::from_secs(60));
+
+    let mut out = String::new();
+    histogram.encode(&mut out);
+    let lines = out.lines().collect::<Vec<_>>();
+
+    assert_eq!(
+        lines.get(..2),
+        Some(
+            [
+                "# HELP latency_seconds Latency.",
+                "# TYPE latency_seconds histogram"
+            ]
+            .as_slice()
+        )
+    );
+    assert!(lines.contains(&"latency_seconds_bucket{route=\"/\",le=\"0.01\"} 0"));
+    assert!(lines.contains(&"latency_seconds_bucket{route=\"/\",le=\"0.025\"} 1"));
+    assert!(lines.contains(&"latency_seconds_bucket{route=\"/\",le=\"0.5\"} 2"));
+    assert!(lines.contains(&"latency_seconds_bucket{route=\"/\",le=\"10\"} 2"));
+    assert!(lines.contains(&"latency_seconds_bucket{route=\"/\",le=\"+Inf\"} 3"));
+    assert!(lines.contains(&"latency_seconds_sum{route=\"/\"} 60.32"));
+    assert!(lines.contains(&"latency_seconds_count{route=\"/\"} 3"));
+}
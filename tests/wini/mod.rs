@@ -1 +1,32 @@
+mod action;
+mod assets;
+mod bundle;
+mod cache;
 mod canonicalize_relative_path;
+mod dependencies;
+mod htmx;
+mod import_map;
+mod imports;
+mod integrity;
+mod linked_file;
+mod metrics;
+mod panic;
+mod precompressed;
+mod session;
+mod streaming;
+mod template;
+mod tsconfig;
+
+
+/// Set the environment read by the server before the tests start, while there is only one thread
+#[ctor::ctor]
+fn init() {
+    // SAFETY: It runs before `main`, so no other thread can read the environment at the same time
+    unsafe {
+        std::env::set_var("ENV_TYPE", "local");
+        std::env::set_var(
+            "SESSION_SECRET",
+            "a secret that is long enough for the tests",
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use {
+    axum::{
+        Router,
+        body::Body,
+        http::{Request, StatusCode},
+        middleware,
+        response::Response,
+        routing::get,
+    },
+    std::hint::black_box,
+    tower::ServiceExt,
+    wini_website::{
+        shared::wini::err::{Backtrace, ServerErrorKind, Trace},
+        utils::wini::panic::catch_panic,
+    },
+};
+
+/// The routes of a page, whose panics are caught like in the router of the pages
+fn app() -> Router {
+    Router::new()
+        .route("/ok", get(|| async { "Fine" }))
+        .route(
+            "/panic",
+            get(|| async { black_box(None::<&str>).expect("The page failed") }),
+        )
+        .route(
+            "/formatted-panic",
+            get(|| {
+                async {
+                    let id = 42;
+                    black_box(None::<&str>).unwrap_or_else(|| panic!("The page {id} failed"))
+                }
+            }),
+        )
+        .layer(middleware::from_fn_with_state(
+            Trace {
+                file_path: "src/pages/test/mod.rs",
+                function_name: "render",
+            },
+            catch_panic,
+        ))
+}
+
+async fn get_response(uri: &str) -> Response {
+    app()
+        .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+}
+
+fn panic_message(resp: &Response) -> String {
+    let backtrace = resp
+        .extensions()
+        .get::<Backtrace>()
+        .expect("The panic is given to the layouts");
+
+    match backtrace.err.as_ref() {
+        ServerErrorKind::Panic(message) => message.clone(),
+        err => panic!("Not a panic: {err:?}"),
+    }
+}
+
+#[tokio::test]
+async fn responses_without_panic_are_untouched() {
+    let resp = get_response("/ok").await;
+
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(resp.extensions().get::<Backtrace>().is_none());
+}
+
+#[tokio::test]
+async fn panics_are_caught_with_their_location() {
+    let resp = get_response("/panic").await;
+
+    assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+    let message = panic_message(&resp);
+    assert!(message.starts_with("The page failed at tests/wini/panic.rs:"));
+
+    let backtrace = resp.extensions().get::<Backtrace>().unwrap();
+    assert_eq!(backtrace.trace.len(), 1);
+    assert_eq!(
+        backtrace.trace.first().map(|trace| trace.function_name),
+        Some("render")
+    );
+}
+
+#[tokio::test]
+async fn formatted_panics_keep_their_message() {
+    let resp = get_response("/formatted-panic").await;
+
+    assert!(panic_message(&resp).starts_with("The page 42 failed at tests/wini/panic.rs:"));
+}
+
+#[tokio::test]
+async fn the_error_page_is_rendered() {
+    let resp = get_response("/panic").await;
+    let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body = String::from_utf8_lossy(&body);
+
+    // The environment is `local`, so the error is detailed
+    assert!(body.contains("The page failed"));
+    assert!(body.contains("src/pages/test/mod.rs"));
+}
@@ -0,0 +1,140 @@
+use {
+    axum::{
+        Router,
+        body::Body,
+        http::{HeaderMap, HeaderValue, Request, header},
+        middleware,
+        response::IntoResponse,
+        routing::get,
+    },
+    tower::ServiceExt,
+    tower_http::compression::CompressionLayer,
+    wini_website::{
+        shared::wini::precompressed::{Encoding, NotForStaticFiles, Precompressed, StaticFile},
+        utils::wini::handling_file::handle_file,
+    },
+};
+
+fn accepting(accept_encoding: &str) -> HeaderMap {
+    HeaderMap::from_iter([(
+        header::ACCEPT_ENCODING,
+        HeaderValue::from_str(accept_encoding).unwrap(),
+    )])
+}
+
+fn best(accept_encoding: &str) -> Option<Encoding> {
+    let content = "body { color: red; }\n".repeat(100);
+    Precompressed::new("/style.css", content.as_bytes())
+        .best(&accepting(accept_encoding))
+        .map(|(encoding, _)| encoding)
+}
+
+#[test]
+fn the_preferred_accepted_encoding_is_chosen() {
+    assert_eq!(best("gzip, deflate, br, zstd"), Some(Encoding::Brotli));
+    assert_eq!(best("gzip, zstd"), Some(Encoding::Zstd));
+    assert_eq!(best("GZIP"), Some(Encoding::Gzip));
+    assert_eq!(best("deflate"), None);
+    assert_eq!(best(""), None);
+}
+
+#[test]
+fn encodings_with_a_zero_quality_are_refused() {
+    assert_eq!(best("br;q=0, gzip;q=0.5"), Some(Encoding::Gzip));
+    assert_eq!(best("br; q=0.0, zstd;q=0, gzip;q=0"), None);
+    assert_eq!(best("br;q=invalid, gzip"), Some(Encoding::Gzip));
+}
+
+#[test]
+fn the_wildcard_accepts_the_encodings_that_are_not_listed() {
+    assert_eq!(best("*"), Some(Encoding::Brotli));
+    assert_eq!(best("br;q=0, *"), Some(Encoding::Zstd));
+    assert_eq!(best("gzip, *;q=0"), Some(Encoding::Gzip));
+}
+
+#[test]
+fn files_that_are_not_smaller_compressed_are_not_precompressed() {
+    assert!(
+        Precompressed::new("/a.txt", b"a")
+            .best(&accepting("*"))
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn static_files_are_not_compressed_again() {
+    let content = "body { color: red; }\n".repeat(100);
+    let app = Router::new()
+        .route(
+            "/static",
+            get({
+                let content = content.clone();
+                move || {
+                    async move {
+                        let mut resp = content.into_response();
+                        resp.extensions_mut().insert(StaticFile);
+                        resp
+                    }
+                }
+            }),
+        )
+        .route("/page", get(move || async move { content }))
+        .layer(CompressionLayer::new().compress_when(NotForStaticFiles));
+
+    let content_encoding = |uri: &'static str| {
+        let app = app.clone();
+        async move {
+            let req = Request::get(uri)
+                .header(header::ACCEPT_ENCODING, "gzip")
+                .body(Body::empty())
+                .unwrap();
+            app.oneshot(req)
+                .await
+                .unwrap()
+                .headers()
+                .get(header::CONTENT_ENCODING)
+                .cloned()
+        }
+    };
+
+    assert_eq!(content_encoding("/static").await, None);
+    assert_eq!(content_encoding("/page").await.unwrap(), "gzip");
+}
+
+#[tokio::test]
+async fn precompressed_files_vary_with_the_accepted_encoding() {
+    let app = Router::new().layer(middleware::from_fn(handle_file));
+    let get = |uri: &'static str, accept_encoding: Option<&'static str>| {
+        let app = app.clone();
+        async move {
+            let mut req = Request::get(uri);
+            if let Some(accept_encoding) = accept_encoding {
+                req = req.header(header::ACCEPT_ENCODING, accept_encoding);
+            }
+            app.oneshot(req.body(Body::empty()).unwrap()).await.unwrap()
+        }
+    };
+
+    // A public file large enough to be precompressed
+    let compressed = get("/codeberg.svg", Some("br")).await;
+    assert_eq!(compressed.headers()[header::CONTENT_ENCODING], "br");
+    assert_eq!(compressed.headers()[header::VARY], "accept-encoding");
+    assert!(compressed.headers().contains_key(header::ETAG));
+
+    let uncompressed = get("/codeberg.svg", None).await;
+    assert!(
+        !uncompressed
+            .headers()
+            .contains_key(header::CONTENT_ENCODING)
+    );
+    assert_eq!(uncompressed.headers()[header::VARY], "accept-encoding");
+    assert_ne!(
+        uncompressed.headers()[header::ETAG],
+        compressed.headers()[header::ETAG]
+    );
+
+    // Too small to be precompressed
+    let small = get("/robots.txt", Some("br")).await;
+    assert!(!small.headers().contains_key(header::CONTENT_ENCODING));
+    assert!(!small.headers().contains_key(header::VARY));
+}
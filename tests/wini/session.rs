@@ -0,0 +1,112 @@
+use {
+    axum::{
+        Router,
+        body::Body,
+        http::{Request, StatusCode, header},
+        middleware,
+        routing::get,
+    },
+    tower::ServiceExt,
+    wini_website::{
+        shared::wini::session::Session,
+        utils::wini::session::{CSRF_HEADER, csrf_protection, session_layer},
+    },
+};
+
+fn app() -> Router {
+    Router::new()
+        .route(
+            "/",
+            get(|session: Session| {
+                async move {
+                    let visits = session.get::<u32>("visits").unwrap_or_default() + 1;
+                    session.insert("visits", visits);
+                    visits.to_string()
+                }
+            })
+            .post(|| async { "posted" }),
+        )
+        .route(
+            "/token",
+            get(|session: Session| async move { session.csrf_token() }),
+        )
+        .layer(middleware::from_fn(csrf_protection))
+        .layer(middleware::from_fn(session_layer))
+}
+
+/// Send a request and return the status, the `name=value` part of the cookie and the body
+async fn send(req: Request<Body>) -> (StatusCode, Option<String>, String) {
+    let resp = app().oneshot(req).await.unwrap();
+    let status = resp.status();
+    let cookie = resp
+        .headers()
+        .get(header::SET_COOKIE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map(str::to_owned);
+    let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+        .await
+        .unwrap();
+
+    (status, cookie, String::from_utf8(body.to_vec()).unwrap())
+}
+
+fn get_with_cookie(uri: &str, cookie: &str) -> Request<Body> {
+    Request::get(uri)
+        .header(header::COOKIE, cookie)
+        .body(Body::empty())
+        .unwrap()
+}
+
+#[tokio::test]
+async fn session_persists_between_requests() {
+    let (_, cookie, body) = send(Request::get("/").body(Body::empty()).unwrap()).await;
+    assert_eq!(body, "1");
+
+    let cookie = cookie.expect("A modified session sets a cookie");
+    let (_, _, body) = send(get_with_cookie("/", &cookie)).await;
+    assert_eq!(body, "2");
+}
+
+#[tokio::test]
+async fn tampered_cookie_gives_a_new_session() {
+    let (_, cookie, _) = send(Request::get("/").body(Body::empty()).unwrap()).await;
+    let mut cookie = cookie.unwrap();
+    cookie.insert(cookie.find('=').unwrap() + 1, 'A');
+
+    let (_, _, body) = send(get_with_cookie("/", &cookie)).await;
+    assert_eq!(body, "1");
+}
+
+#[tokio::test]
+async fn post_requires_csrf_token() {
+    let (_, cookie, token) = send(Request::get("/token").body(Body::empty()).unwrap()).await;
+    let cookie = cookie.unwrap();
+
+    let without_token = Request::post("/")
+        .header(header::COOKIE, &cookie)
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(send(without_token).await.0, StatusCode::FORBIDDEN);
+
+    let with_wrong_token = Request::post("/")
+        .header(header::COOKIE, &cookie)
+        .header(CSRF_HEADER, "wrong")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(send(with_wrong_token).await.0, StatusCode::FORBIDDEN);
+
+    let with_header = Request::post("/")
+        .header(header::COOKIE, &cookie)
+        .header(CSRF_HEADER, &token)
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(send(with_header).await.0, StatusCode::OK);
+
+    let with_form = Request::post("/")
+        .header(header::COOKIE, &cookie)
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(Body::from(format!("name=wini&_csrf={token}")))
+        .unwrap();
+    assert_eq!(send(with_form).await.0, StatusCode::OK);
+}
@@ -0,0 +1,176 @@
+use {
+    axum::{
+        Router,
+        body::Body,
+        http::{Request, header},
+        middleware,
+        response::Html,
+        routing::get,
+    },
+    http_body_util::BodyExt,
+    maud::{Markup, html},
+    std::{
+        hint::black_box,
+        sync::{Arc, Mutex},
+    },
+    tokio::sync::oneshot,
+    tower::ServiceExt,
+    tower_http::compression::{CompressionLayer, DefaultPredicate, Predicate},
+    wini_website::{
+        shared::wini::streaming::{NotForStreamedPages, defer},
+        template::template,
+    },
+};
+
+/// A page with a component deferred until `release` is sent
+fn app() -> (Router, oneshot::Sender<()>) {
+    let (release, released) = oneshot::channel::<()>();
+    let released = Arc::new(Mutex::new(Some(released)));
+
+    let app = Router::new()
+        .route(
+            "/page",
+            get(move || {
+                let released = released.lock().unwrap().take().unwrap();
+
+                async move {
+                    let slow = defer(async move {
+                        let _ = released.await;
+                        html! { p { "Slow" } }
+                    })
+                    .await;
+
+                    Html(
+                        html! {
+                            p { "Fast" }
+                            (slow)
+                        }
+                        .into_string(),
+                    )
+                }
+            }),
+        )
+        .route(
+            "/panicking",
+            get(|| {
+                async {
+                    let failing =
+                        defer(async { black_box(None::<Markup>).expect("The component failed") })
+                            .await;
+
+                    Html(html! { p { "Fast" } (failing) }.into_string())
+                }
+            }),
+        )
+        .layer(middleware::from_fn(template))
+        // Like in the server
+        .layer(
+            CompressionLayer::new().compress_when(DefaultPredicate::new().and(NotForStreamedPages)),
+        );
+
+    (app, release)
+}
+
+/// Read the body until `expected` is sent
+async fn read_until(body: &mut Body, expected: &str) -> String {
+    let mut sent = String::new();
+    while !sent.contains(expected) {
+        let frame = body.frame().await.unwrap().unwrap();
+        sent.push_str(std::str::from_utf8(frame.data_ref().unwrap()).unwrap());
+    }
+    sent
+}
+
+#[tokio::test]
+async fn the_page_is_sent_before_the_deferred_components() {
+    let (app, release) = app();
+    let resp = app
+        .oneshot(Request::get("/page").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    assert!(!resp.headers().contains_key(header::CONTENT_LENGTH));
+
+    let mut body = resp.into_body();
+    let sent = read_until(&mut body, "<p>Fast</p>").await;
+
+    assert!(sent.contains("</head>"));
+    assert!(sent.contains(
+        r#"<wini-deferred id="wini-deferred-0" style="display: contents"></wini-deferred>"#
+    ));
+    assert!(!sent.contains("Slow"));
+
+    release.send(()).unwrap();
+    let rest = body.collect().await.unwrap().to_bytes();
+    let rest = std::str::from_utf8(&rest).unwrap();
+
+    assert!(rest.contains(r#"<template id="wini-deferred-0-content"><p>Slow</p></template>"#));
+    assert!(rest.ends_with("</body></html>"));
+}
+
+#[tokio::test]
+async fn compressible_pages_are_streamed_without_being_compressed() {
+    let (app, release) = app();
+    let req = Request::get("/page")
+        .header(header::ACCEPT_ENCODING, "br")
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.oneshot(req).await.unwrap();
+
+    assert!(!resp.headers().contains_key(header::CONTENT_ENCODING));
+
+    // The page is sent while the deferred component is still waiting
+    let mut body = resp.into_body();
+    read_until(&mut body, "<p>Fast</p>").await;
+
+    release.send(()).unwrap();
+    read_until(&mut body, "<p>Slow</p>").await;
+}
+
+#[tokio::test]
+async fn panicking_components_are_replaced_by_the_error() {
+    let (app, _release) = app();
+    let body = app
+        .oneshot(Request::get("/panicking").body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+        .into_body()
+        .collect()
+        .await
+        .unwrap()
+        .to_bytes();
+    let body = std::str::from_utf8(&body).unwrap();
+
+    let (_, content) = body
+        .split_once(r#"<template id="wini-deferred-0-content">"#)
+        .unwrap();
+    assert!(content.contains(r#"class="wini-error""#));
+    // The environment is `local`, so the error is detailed
+    assert!(content.contains("The component failed"));
+}
+
+#[tokio::test]
+async fn fragments_wait_for_the_deferred_components() {
+    let (app, release) = app();
+    release.send(()).unwrap();
+
+    let req = Request::get("/page")
+        .header("hx-request", "true")
+        .header("hx-target", "content")
+        .body(Body::empty())
+        .unwrap();
+    let body = app
+        .oneshot(req)
+        .await
+        .unwrap()
+        .into_body()
+        .collect()
+        .await
+        .unwrap()
+        .to_bytes();
+
+    assert_eq!(
+        &body[..],
+        br#"<p>Fast</p><wini-deferred id="wini-deferred-0" style="display: contents"><p>Slow</p></wini-deferred>"#
+    );
+}
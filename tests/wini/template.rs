@@ -0,0 +1,218 @@
+use {
+    axum::{
+        Json,
+        Router,
+        body::Body,
+        http::{Request, StatusCode, header},
+        middleware,
+        response::{Html, IntoResponse, Redirect},
+        routing::get,
+    },
+    maud::html,
+    std::collections::HashMap,
+    tower::ServiceExt,
+    wini_website::{
+        shared::wini::{
+            assets::Manifest,
+            import_map::ImportMap,
+            layer::Tags,
+            linked_file::{FileKind, LinkedFile, Placement},
+            tsconfig::TsConfigPaths,
+        },
+        template::{html::document, template},
+    },
+};
+
+fn app() -> Router {
+    Router::new()
+        .route("/page", get(|| async { Html("<p>Hello</p>") }))
+        .route(
+            "/json",
+            get(|| async { Json(serde_json::json!({ "hello": "world" })) }),
+        )
+        .route(
+            "/binary",
+            get(|| {
+                async {
+                    (
+                        [(header::CONTENT_TYPE, "application/octet-stream")],
+                        vec![0xff, 0xfe, 0x00],
+                    )
+                }
+            }),
+        )
+        .route("/redirect", get(|| async { Redirect::to("/page") }))
+        .route(
+            "/titled",
+            get(|| {
+                async {
+                    let mut resp = Html("<p>Hello</p>").into_response();
+                    resp.extensions_mut().insert(Tags::from([
+                        ("title", "Hello".into()),
+                        ("description", "A greeting".into()),
+                    ]));
+                    resp
+                }
+            }),
+        )
+        .layer(middleware::from_fn(template))
+}
+
+async fn body_of(app: Router, req: Request<Body>) -> (StatusCode, Vec<u8>) {
+    let resp = app.oneshot(req).await.unwrap();
+    let status = resp.status();
+    let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+        .await
+        .unwrap();
+
+    (status, body.to_vec())
+}
+
+fn get_request(uri: &str) -> Request<Body> {
+    Request::get(uri).body(Body::empty()).unwrap()
+}
+
+#[tokio::test]
+async fn html_is_wrapped_in_the_document() {
+    let (status, body) = body_of(app(), get_request("/page")).await;
+
+    assert_eq!(status, StatusCode::OK);
+    let body = String::from_utf8(body).unwrap();
+    assert!(body.starts_with("<!DOCTYPE html>"));
+    assert!(body.contains("<p>Hello</p>"));
+}
+
+#[tokio::test]
+async fn fragment_requests_get_the_content_only() {
+    let req = Request::get("/page")
+        .header("hx-request", "true")
+        .header("hx-target", "content")
+        .body(Body::empty())
+        .unwrap();
+    let resp = app().oneshot(req).await.unwrap();
+
+    assert_eq!(resp.headers()[header::VARY], "HX-Request, HX-Target");
+    let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(&body[..], b"<p>Hello</p>");
+}
+
+#[tokio::test]
+async fn fragments_update_the_title_and_the_meta_tags() {
+    let req = Request::get("/titled")
+        .header("hx-request", "true")
+        .header("hx-target", "content")
+        .body(Body::empty())
+        .unwrap();
+    let (_, body) = body_of(app(), req).await;
+    let body = String::from_utf8(body).unwrap();
+
+    assert!(body.starts_with("<p>Hello</p><title>Hello</title>"));
+    assert!(body.contains(
+        r#"<meta id="meta-description" name="description" content="A greeting" hx-swap-oob="true">"#
+    ));
+
+    let (_, body) = body_of(app(), get_request("/titled")).await;
+    let body = String::from_utf8(body).unwrap();
+
+    assert!(
+        body.contains(r#"<meta id="meta-description" name="description" content="A greeting">"#)
+    );
+}
+
+#[tokio::test]
+async fn history_restore_requests_get_the_full_page() {
+    let req = Request::get("/page")
+        .header("hx-request", "true")
+        .header("hx-target", "content")
+        .header("hx-history-restore-request", "true")
+        .body(Body::empty())
+        .unwrap();
+    let (_, body) = body_of(app(), req).await;
+
+    assert!(body.starts_with(b"<!DOCTYPE html>"));
+}
+
+#[tokio::test]
+async fn other_responses_are_untouched() {
+    let (_, body) = body_of(app(), get_request("/json")).await;
+    assert_eq!(body, br#"{"hello":"world"}"#);
+
+    let (status, body) = body_of(app(), get_request("/binary")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, [0xff, 0xfe, 0x00]);
+
+    let (status, body) = body_of(app(), get_request("/redirect")).await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    assert!(body.is_empty());
+}
+
+/// The `<head>` and the end of the `<body>` of a document linking a style sheet, a classic script, a
+/// module and a script at the end of the body
+fn document_with_scripts(import_map: Option<&ImportMap>) -> (String, String) {
+    let files = vec![
+        LinkedFile::new("/modules/classic/classic.min.js"),
+        LinkedFile {
+            kind: Some(FileKind::Module),
+            ..LinkedFile::new("/src/app.js")
+        },
+        LinkedFile::new("/src/style.css"),
+        LinkedFile {
+            placement: Placement::Body,
+            ..LinkedFile::new("/src/end.js")
+        },
+    ];
+
+    document(
+        files,
+        vec!["/src/dependency.js".to_owned()],
+        &html! {},
+        None,
+        import_map,
+    )
+}
+
+#[test]
+fn modules_are_preloaded_after_the_import_map() {
+    let scripts = [
+        "/src/app.js".to_owned(),
+        "/src/dependency.js".to_owned(),
+    ];
+    let import_map = ImportMap::new(
+        &Manifest::default(),
+        &scripts,
+        &TsConfigPaths::default(),
+        &HashMap::new(),
+    );
+    let (head, end) = document_with_scripts(Some(&import_map));
+
+    let position = |tag: &str| {
+        head.find(tag)
+            .unwrap_or_else(|| panic!("`{tag}` isn't in `{head}`"))
+    };
+    let style_sheet = position(r#"<link rel="stylesheet" href="/src/style.css">"#);
+    let import_map = position(r#"<script type="importmap">"#);
+    let preload = position(r#"<link rel="modulepreload" href="/src/dependency.js">"#);
+    let classic = position(r#"<script src="/modules/classic/classic.min.js""#);
+    let module = position(r#"<script type="module" src="/src/app.js">"#);
+
+    // The scripts are after the other files, in their order
+    assert!(style_sheet < import_map);
+    assert!(import_map < preload);
+    assert!(preload < classic);
+    assert!(classic < module);
+
+    assert!(!head.contains("/src/end.js"));
+    assert!(end.contains(r#"src="/src/end.js""#));
+}
+
+#[test]
+fn modules_are_not_preloaded_without_an_import_map() {
+    let (head, _) = document_with_scripts(None);
+
+    assert!(!head.contains("importmap"));
+    assert!(!head.contains("modulepreload"));
+    assert!(head.contains(r#"<script type="module" src="/src/app.js">"#));
+    assert!(!head.contains(r#"type="module" src="/modules/classic/classic.min.js""#));
+}
@@ -0,0 +1,177 @@
+use {
+    std::path::{Path, PathBuf},
+    wini_website::shared::wini::tsconfig::{TsConfigPaths, strip_jsonc},
+};
+
+
+/// Write the files of a project in a temporary directory, and return it
+fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("wini-tsconfig-{name}"));
+    let _ = std::fs::remove_dir_all(&directory);
+
+    for (path, content) in files {
+        let path = directory.join(path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).unwrap();
+        }
+        std::fs::write(path, content).unwrap();
+    }
+
+    directory
+}
+
+fn paths(candidates: Option<Vec<PathBuf>>, directory: &Path) -> Option<Vec<String>> {
+    candidates.map(|candidates| {
+        candidates
+            .iter()
+            .map(|path| {
+                path.strip_prefix(directory)
+                    .unwrap_or(path)
+                    .display()
+                    .to_string()
+            })
+            .collect()
+    })
+}
+
+#[test]
+fn comments_and_trailing_commas_are_removed() {
+    let jsonc = r#"{
+        // A comment
+        "compilerOptions": {
+            /* Another one, with a "string" */
+            "paths": { "~/*": ["./src/*",], },
+            "url": "https://example.com/*", // Not a comment
+        },
+    }"#;
+
+    let json = serde_json::from_str::<serde_json::Value>(&strip_jsonc(jsonc)).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "compilerOptions": {
+                "paths": { "~/*": ["./src/*"] },
+                "url": "https://example.com/*",
+            },
+        })
+    );
+
+    // The lines stay the same
+    assert_eq!(strip_jsonc(jsonc).lines().count(), jsonc.lines().count());
+}
+
+#[test]
+fn extended_options_are_overridden() {
+    let directory = project(
+        "extends",
+        &[
+            (
+                "tsconfig.json",
+                r#"{ "extends": "./config/base", "compilerOptions": { "baseUrl": "./src" } }"#,
+            ),
+            (
+                "config/base.json",
+                r#"{
+                    "extends": "@scope/config",
+                    "compilerOptions": { "baseUrl": ".", "paths": { "~/*": ["./lib/*"] } },
+                }"#,
+            ),
+            (
+                "config/node_modules/@scope/config/tsconfig.json",
+                r#"{ "compilerOptions": { "paths": { "x": ["./x"] } } }"#,
+            ),
+        ],
+    );
+    let tsconfig = TsConfigPaths::load(&directory.join("tsconfig.json"));
+
+    // The paths are relative to the `baseUrl` that overrides the one of the file that sets them
+    assert_eq!(tsconfig.base_url(), Some(directory.join("src").as_path()));
+    assert_eq!(
+        paths(tsconfig.mapped("~/utils/date"), &directory),
+        Some(vec!["src/lib/utils/date".to_owned()])
+    );
+    // The `paths` of the extended files are overridden as a whole
+    assert_eq!(tsconfig.mapped("x"), None);
+    assert_eq!(
+        tsconfig.from_base_url("utils/date"),
+        Some(directory.join("src/utils/date"))
+    );
+}
+
+#[test]
+fn extended_files_can_be_written_without_their_extension() {
+    let directory = project(
+        "extends-without-extension",
+        &[
+            ("tsconfig.json", r#"{ "extends": "./tsconfig.base" }"#),
+            (
+                "tsconfig.base.json",
+                r#"{ "compilerOptions": { "baseUrl": "./src" } }"#,
+            ),
+        ],
+    );
+    let tsconfig = TsConfigPaths::load(&directory.join("tsconfig.json"));
+
+    assert_eq!(tsconfig.base_url(), Some(directory.join("src").as_path()));
+}
+
+#[test]
+fn circular_extends_are_ignored() {
+    let directory = project(
+        "circular",
+        &[
+            ("tsconfig.json", r#"{ "extends": "./other.json" }"#),
+            ("other.json", r#"{ "extends": "./tsconfig.json" }"#),
+        ],
+    );
+    let tsconfig = TsConfigPaths::load(&directory.join("tsconfig.json"));
+
+    assert!(tsconfig.patterns().is_empty());
+    assert_eq!(tsconfig.base_url(), None);
+}
+
+#[test]
+fn the_most_specific_pattern_applies() {
+    let directory = project(
+        "patterns",
+        &[(
+            "tsconfig.json",
+            r#"{
+                "compilerOptions": {
+                    "paths": {
+                        "~/*": ["./src/*"],
+                        "~/utils/*": ["./src/utils/*", "./shared/utils/*"],
+                        "~/utils/date": ["./vendor/date.js"],
+                        "*.css": ["./styles/*.css"],
+                    },
+                },
+            }"#,
+        )],
+    );
+    let tsconfig = TsConfigPaths::load(&directory.join("tsconfig.json"));
+    let mapped = |import| paths(tsconfig.mapped(import), &directory);
+
+    // Without `baseUrl`, the paths are relative to the `tsconfig.json`
+    assert_eq!(tsconfig.base_url(), None);
+    assert_eq!(
+        mapped("~/utils/date"),
+        Some(vec!["vendor/date.js".to_owned()])
+    );
+    assert_eq!(
+        mapped("~/utils/time"),
+        Some(vec![
+            "src/utils/time".to_owned(),
+            "shared/utils/time".to_owned()
+        ])
+    );
+    assert_eq!(
+        mapped("~/pages/home"),
+        Some(vec!["src/pages/home".to_owned()])
+    );
+    assert_eq!(
+        mapped("theme.css"),
+        Some(vec!["styles/theme.css".to_owned()])
+    );
+    assert_eq!(mapped("htmx.org"), None);
+    assert_eq!(tsconfig.from_base_url("htmx.org"), None);
+}